@@ -9,11 +9,15 @@
 //! Currently, communication from the procedural macro and test-suite is implemented via files in the `target/mutagen` directory.
 //! The communication to the test-suite is implemented via environment variables
 mod coverage;
+mod json_report;
 mod mutagen_files;
 mod mutation;
 mod report;
 
 pub use coverage::{CoverageCollection, CoverageHit};
+pub use json_report::{
+    JsonMutantStatus, JsonMutationResult, JsonReport, JSON_REPORT_SCHEMA_VERSION,
+};
 pub use mutagen_files::*;
-pub use mutation::{BakedMutation, Mutation};
-pub use report::{MutagenReport, MutantStatus};
+pub use mutation::{BakedMutation, Mutation, SourceLocation};
+pub use report::{KillDetails, MutagenReport, MutantStatus, ReportSummary, ResourceLimit};
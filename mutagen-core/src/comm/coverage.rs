@@ -1,3 +1,5 @@
+use std::collections::{BTreeSet, HashMap};
+
 use super::BakedMutation;
 use serde::{Deserialize, Serialize};
 
@@ -5,6 +7,19 @@ use serde::{Deserialize, Serialize};
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CoverageHit {
     pub mutator_id: usize,
+    /// name of the test that hit the mutator.
+    ///
+    /// This is the name of the thread that hit the mutator, which is the test name for tests of the default test harness.
+    #[serde(default)]
+    pub test_name: Option<String>,
+}
+
+impl CoverageHit {
+    /// Returns the name of the test that hit the mutator, if the hit happened inside a test.
+    fn test_name(&self) -> Option<&str> {
+        // the main thread is not a test, but might be a custom test harness
+        self.test_name.as_deref().filter(|name| *name != "main")
+    }
 }
 
 /// A collection that tracks which mutations have been covered.
@@ -14,6 +29,10 @@ pub struct CoverageHit {
 pub struct CoverageCollection {
     num_covered: usize,
     coverage: Vec<bool>,
+    /// names of the tests that cover each mutation
+    covering_tests: Vec<BTreeSet<String>>,
+    /// mutations that are covered outside of any known test
+    covered_outside_tests: Vec<bool>,
 }
 
 impl CoverageCollection {
@@ -22,6 +41,8 @@ impl CoverageCollection {
         Self {
             num_covered: 0,
             coverage: vec![false; num_mutations + 1],
+            covering_tests: vec![BTreeSet::new(); num_mutations + 1],
+            covered_outside_tests: vec![false; num_mutations + 1],
         }
     }
 
@@ -31,24 +52,33 @@ impl CoverageCollection {
         hits: &[CoverageHit],
         mutations: &[BakedMutation],
     ) -> Self {
-        let mut coverage = vec![false; num_mutations + 1];
-        let mut num_covered = 0;
+        let mut collection = Self::new_empty(num_mutations);
+
+        let mut mutations_per_mutator = HashMap::<usize, Vec<usize>>::new();
+        for m in mutations {
+            mutations_per_mutator
+                .entry(m.mutator_id())
+                .or_default()
+                .push(m.id());
+        }
 
         for hit in hits {
-            if !coverage[hit.mutator_id] {
-                for m in mutations {
-                    if m.mutator_id() == hit.mutator_id {
-                        num_covered += 1;
-                        coverage[m.id()] = true;
+            let m_ids = match mutations_per_mutator.get(&hit.mutator_id) {
+                Some(m_ids) => m_ids,
+                None => continue,
+            };
+            for &m_id in m_ids {
+                collection.set_covered(m_id);
+                match hit.test_name() {
+                    Some(test_name) => {
+                        collection.covering_tests[m_id].insert(test_name.to_owned());
                     }
+                    None => collection.covered_outside_tests[m_id] = true,
                 }
             }
         }
 
-        Self {
-            num_covered,
-            coverage,
-        }
+        collection
     }
 
     /// Merge multiple coverage collections into a single one.
@@ -56,21 +86,25 @@ impl CoverageCollection {
         num_mutations: usize,
         coverages: impl IntoIterator<Item = &'a CoverageCollection>,
     ) -> Self {
-        let mut coverage = vec![false; num_mutations + 1];
-        let mut num_covered = 0;
+        let mut collection = Self::new_empty(num_mutations);
 
         for c in coverages {
             for m_id in 1..=num_mutations {
-                if c.is_covered(m_id) && !coverage[m_id] {
-                    num_covered += 1;
-                    coverage[m_id] = true;
+                if c.is_covered(m_id) {
+                    collection.set_covered(m_id);
+                    collection.covering_tests[m_id].extend(c.covering_tests[m_id].iter().cloned());
+                    collection.covered_outside_tests[m_id] |= c.covered_outside_tests[m_id];
                 }
             }
         }
 
-        Self {
-            num_covered,
-            coverage,
+        collection
+    }
+
+    fn set_covered(&mut self, m_id: usize) {
+        if !self.coverage[m_id] {
+            self.num_covered += 1;
+            self.coverage[m_id] = true;
         }
     }
 
@@ -83,6 +117,18 @@ impl CoverageCollection {
     pub fn num_covered(&self) -> usize {
         self.num_covered
     }
+
+    /// Returns the names of the tests that cover the given mutation.
+    ///
+    /// Returns `None` if the mutation is covered outside of a test (e.g. in a thread spawned by a test).
+    /// In this case, it is unknown which tests cover the mutation.
+    pub fn covering_tests(&self, m_id: usize) -> Option<&BTreeSet<String>> {
+        if self.covered_outside_tests[m_id] {
+            None
+        } else {
+            Some(&self.covering_tests[m_id])
+        }
+    }
 }
 
 #[cfg(test)]
@@ -90,6 +136,22 @@ mod tests {
     use super::*;
     use crate::comm::Mutation;
 
+    impl CoverageHit {
+        fn stub(mutator_id: usize) -> Self {
+            Self {
+                mutator_id,
+                test_name: None,
+            }
+        }
+
+        fn in_test(mutator_id: usize, test_name: &str) -> Self {
+            Self {
+                mutator_id,
+                test_name: Some(test_name.to_owned()),
+            }
+        }
+    }
+
     #[test]
     fn coverage_collection_empty() {
         let c = CoverageCollection::new_empty(2);
@@ -102,7 +164,7 @@ mod tests {
     fn coverage_collection_single_covered() {
         let c = CoverageCollection::from_coverage_hits(
             2,
-            &[CoverageHit { mutator_id: 1 }],
+            &[CoverageHit::stub(1)],
             &[Mutation::new_stub().with_id(1, 1)],
         );
 
@@ -114,7 +176,7 @@ mod tests {
     fn coverage_collection_covered_all_mutations_of_mutator() {
         let c = CoverageCollection::from_coverage_hits(
             2,
-            &[CoverageHit { mutator_id: 1 }],
+            &[CoverageHit::stub(1)],
             &[
                 Mutation::new_stub().with_id(1, 1),
                 Mutation::new_stub().with_id(2, 1),
@@ -134,10 +196,8 @@ mod tests {
             Mutation::new_stub().with_id(3, 3),
             Mutation::new_stub().with_id(4, 3),
         ];
-        let c1 =
-            CoverageCollection::from_coverage_hits(4, &[CoverageHit { mutator_id: 2 }], &mutations);
-        let c2 =
-            CoverageCollection::from_coverage_hits(4, &[CoverageHit { mutator_id: 3 }], &mutations);
+        let c1 = CoverageCollection::from_coverage_hits(4, &[CoverageHit::stub(2)], &mutations);
+        let c2 = CoverageCollection::from_coverage_hits(4, &[CoverageHit::stub(3)], &mutations);
 
         let c = CoverageCollection::merge(4, &[c1, c2]);
 
@@ -146,4 +206,54 @@ mod tests {
         assert!(c.is_covered(4));
         assert_eq!(c.num_covered(), 3);
     }
+
+    #[test]
+    fn coverage_collection_covering_tests() {
+        let mutations = [
+            Mutation::new_stub().with_id(1, 1),
+            Mutation::new_stub().with_id(2, 1),
+            Mutation::new_stub().with_id(3, 3),
+        ];
+        let c = CoverageCollection::from_coverage_hits(
+            3,
+            &[
+                CoverageHit::in_test(1, "test_a"),
+                CoverageHit::in_test(1, "test_b"),
+            ],
+            &mutations,
+        );
+
+        let expected = ["test_a", "test_b"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<BTreeSet<_>>();
+        assert_eq!(c.covering_tests(1), Some(&expected));
+        assert_eq!(c.covering_tests(2), Some(&expected));
+        assert_eq!(c.covering_tests(3), Some(&BTreeSet::new()));
+    }
+
+    #[test]
+    fn coverage_collection_covered_outside_tests() {
+        let mutations = [Mutation::new_stub().with_id(1, 1)];
+        let c = CoverageCollection::from_coverage_hits(
+            1,
+            &[CoverageHit::in_test(1, "test_a"), CoverageHit::stub(1)],
+            &mutations,
+        );
+
+        assert!(c.is_covered(1));
+        assert_eq!(c.covering_tests(1), None);
+    }
+
+    #[test]
+    fn coverage_collection_main_thread_is_not_a_test() {
+        let mutations = [Mutation::new_stub().with_id(1, 1)];
+        let c = CoverageCollection::from_coverage_hits(
+            1,
+            &[CoverageHit::in_test(1, "main")],
+            &mutations,
+        );
+
+        assert_eq!(c.covering_tests(1), None);
+    }
 }
@@ -0,0 +1,272 @@
+//! Machine-readable report of the mutation analysis.
+//!
+//! The runner writes this report to `target/mutagen/report.json` after every run.
+//! The field `schema_version` is increased on every incompatible change of the format, e.g. a new status of mutants.
+//! Readers check the version before reading the rest of the report, so that newer reports are rejected with a clear error.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use super::{
+    BakedMutation, KillDetails, MutagenReport, MutantStatus, Mutation, ReportSummary, ResourceLimit,
+};
+
+/// Current version of the schema of the json report.
+///
+/// Version 2 added the status `unviable`, version 3 the status `ignored` and version 4 the status `limit_exceeded`.
+pub const JSON_REPORT_SCHEMA_VERSION: u32 = 4;
+
+/// Report of all mutations and their results
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JsonReport {
+    pub schema_version: u32,
+    pub summary: ReportSummary,
+    /// results of all mutations, sorted by mutation-id
+    pub mutations: Vec<JsonMutationResult>,
+}
+
+/// Description and result of a single mutation
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JsonMutationResult {
+    pub id: usize,
+    pub mutator_id: usize,
+    pub mutator: String,
+    pub source_file: PathBuf,
+    /// location of the mutation in the format `line:column-line:column`
+    pub location: String,
+    pub fn_name: Option<String>,
+    pub impl_name: Option<String>,
+    pub original_code: String,
+    pub mutated_code: String,
+    pub description: String,
+    pub status: JsonMutantStatus,
+    /// exit code of the test suite that killed the mutant, if any
+    pub exit_code: Option<i32>,
+    /// the resource limit the mutant exceeded, if any
+    #[serde(default)]
+    pub exceeded_limit: Option<ResourceLimit>,
+    /// names of the tests that killed the mutant
+    #[serde(default)]
+    pub failed_tests: Vec<String>,
+    /// the first panic message of the tests that killed the mutant, possibly truncated
+    #[serde(default)]
+    pub panic_message: Option<String>,
+}
+
+/// Result of a single mutation as written to the json report
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JsonMutantStatus {
+    NotCovered,
+    Survived,
+    Killed,
+    Timeout,
+    LimitExceeded,
+    Skipped,
+    Unviable,
+    Ignored,
+}
+
+impl JsonReport {
+    pub fn new(report: &MutagenReport) -> Self {
+        Self {
+            schema_version: JSON_REPORT_SCHEMA_VERSION,
+            summary: report.summary(),
+            mutations: report
+                .mutant_results()
+                .into_iter()
+                .map(|(m, s)| JsonMutationResult::new(m, s, report.kill_details(m.id())))
+                .collect(),
+        }
+    }
+}
+
+impl JsonMutationResult {
+    pub fn new(
+        m: &BakedMutation,
+        status: MutantStatus,
+        kill_details: Option<&KillDetails>,
+    ) -> Self {
+        let exceeded_limit = match status {
+            MutantStatus::LimitExceeded(limit) => Some(limit),
+            _ => None,
+        };
+        let (status, exit_code) = match status {
+            MutantStatus::NotCovered => (JsonMutantStatus::NotCovered, None),
+            MutantStatus::Survived => (JsonMutantStatus::Survived, None),
+            MutantStatus::Killed(exit_code) => (JsonMutantStatus::Killed, exit_code),
+            MutantStatus::Timeout => (JsonMutantStatus::Timeout, None),
+            MutantStatus::LimitExceeded(_) => (JsonMutantStatus::LimitExceeded, None),
+            MutantStatus::Skipped => (JsonMutantStatus::Skipped, None),
+            MutantStatus::Unviable => (JsonMutantStatus::Unviable, None),
+            MutantStatus::Ignored => (JsonMutantStatus::Ignored, None),
+        };
+        Self {
+            id: m.id(),
+            mutator_id: m.mutator_id(),
+            mutator: m.mutator_name().to_owned(),
+            source_file: m.source_file().to_owned(),
+            location: m.location_in_file().to_owned(),
+            fn_name: m.fn_name().map(|s| s.to_owned()),
+            impl_name: m.impl_name().map(|s| s.to_owned()),
+            original_code: m.original_code().to_owned(),
+            mutated_code: m.mutated_code().to_owned(),
+            description: m.mutation_description(),
+            status,
+            exit_code,
+            exceeded_limit,
+            failed_tests: kill_details
+                .map(|d| d.failed_tests.clone())
+                .unwrap_or_default(),
+            panic_message: kill_details.and_then(|d| d.panic_message.clone()),
+        }
+    }
+
+    /// Reconstructs the mutation described by this result.
+    pub fn mutation(&self) -> BakedMutation {
+        Mutation {
+            impl_name: self.impl_name.clone(),
+            fn_name: self.fn_name.clone(),
+            mutator: self.mutator.clone(),
+            original_code: self.original_code.clone(),
+            mutated_code: self.mutated_code.clone(),
+            source_file: self.source_file.clone(),
+            location_in_file: self.location.clone(),
+        }
+        .with_id(self.id, self.mutator_id)
+    }
+
+    pub fn mutant_status(&self) -> MutantStatus {
+        match self.status {
+            JsonMutantStatus::NotCovered => MutantStatus::NotCovered,
+            JsonMutantStatus::Survived => MutantStatus::Survived,
+            JsonMutantStatus::Killed => MutantStatus::Killed(self.exit_code),
+            JsonMutantStatus::Timeout => MutantStatus::Timeout,
+            JsonMutantStatus::LimitExceeded => match self.exceeded_limit {
+                Some(limit) => MutantStatus::LimitExceeded(limit),
+                None => MutantStatus::Killed(self.exit_code),
+            },
+            JsonMutantStatus::Skipped => MutantStatus::Skipped,
+            JsonMutantStatus::Unviable => MutantStatus::Unviable,
+            JsonMutantStatus::Ignored => MutantStatus::Ignored,
+        }
+    }
+
+    /// Returns the details about the killing of the mutant, if any have been recorded.
+    pub fn kill_details(&self) -> Option<KillDetails> {
+        if self.failed_tests.is_empty() && self.panic_message.is_none() {
+            None
+        } else {
+            Some(KillDetails {
+                failed_tests: self.failed_tests.clone(),
+                panic_message: self.panic_message.clone(),
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mutant_status_roundtrip() {
+        let mutation = Mutation::new_stub().with_id(1, 1);
+        for status in [
+            MutantStatus::Unviable,
+            MutantStatus::Ignored,
+            MutantStatus::LimitExceeded(ResourceLimit::Memory),
+            MutantStatus::LimitExceeded(ResourceLimit::FileSize),
+        ] {
+            let result = JsonMutationResult::new(&mutation, status, None);
+            let json = serde_json::to_string(&result).unwrap();
+            let result: JsonMutationResult = serde_json::from_str(&json).unwrap();
+
+            assert_eq!(result.mutant_status(), status);
+        }
+    }
+
+    #[test]
+    fn mutation_result_roundtrip() {
+        let mutation = Mutation::new_stub()
+            .stub_source_file("src/lib.rs")
+            .stub_fn_name("foo")
+            .stub_mutator("lit_int")
+            .stub_code("1", "2")
+            .stub_location("3:4-3:5")
+            .with_id(7, 5);
+        let kill_details = KillDetails {
+            failed_tests: vec!["tests::foo".to_owned()],
+            panic_message: None,
+        };
+        let result = JsonMutationResult::new(
+            &mutation,
+            MutantStatus::Killed(Some(101)),
+            Some(&kill_details),
+        );
+
+        assert_eq!(result.mutation(), mutation);
+        assert_eq!(result.mutant_status(), MutantStatus::Killed(Some(101)));
+        assert_eq!(result.kill_details(), Some(kill_details));
+    }
+
+    #[test]
+    fn limit_exceeded_roundtrip() {
+        let mutation = Mutation::new_stub()
+            .stub_source_file("src/lib.rs")
+            .stub_location("3:4-3:5")
+            .with_id(1, 1);
+        let status = MutantStatus::LimitExceeded(ResourceLimit::Memory);
+        let result = JsonMutationResult::new(&mutation, status, None);
+
+        assert_eq!(result.status, JsonMutantStatus::LimitExceeded);
+        assert_eq!(result.exceeded_limit, Some(ResourceLimit::Memory));
+        assert_eq!(result.mutant_status(), status);
+    }
+
+    /// schema version that introduced the given status
+    ///
+    /// The match is exhaustive, so that every new status has to be listed here with a new version.
+    fn introduced_in(status: JsonMutantStatus) -> u32 {
+        use JsonMutantStatus::*;
+        match status {
+            NotCovered | Survived | Killed | Timeout | Skipped => 1,
+            Unviable => 2,
+            Ignored => 3,
+            LimitExceeded => 4,
+        }
+    }
+
+    #[test]
+    fn new_statuses_bump_schema_version() {
+        let mutation = Mutation::new_stub().with_id(1, 1);
+        let mut versions = vec![];
+        for status in [
+            MutantStatus::NotCovered,
+            MutantStatus::Survived,
+            MutantStatus::Killed(Some(101)),
+            MutantStatus::Timeout,
+            MutantStatus::LimitExceeded(ResourceLimit::Memory),
+            MutantStatus::Skipped,
+            MutantStatus::Unviable,
+            MutantStatus::Ignored,
+        ] {
+            let json =
+                serde_json::to_value(JsonMutationResult::new(&mutation, status, None)).unwrap();
+            let json_status: JsonMutantStatus =
+                serde_json::from_value(json["status"].clone()).unwrap();
+            versions.push(introduced_in(json_status));
+        }
+
+        assert_eq!(
+            versions.iter().max().copied(),
+            Some(JSON_REPORT_SCHEMA_VERSION),
+            "the newest status must have been introduced by the current schema version"
+        );
+        assert_eq!(
+            (JSON_REPORT_SCHEMA_VERSION, versions.len()),
+            (4, 8),
+            "a new status requires a new schema version"
+        );
+    }
+}
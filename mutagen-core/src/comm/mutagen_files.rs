@@ -1,16 +1,29 @@
 use anyhow::{bail, Context, Result};
+use lazy_static::lazy_static;
 use serde::{de::DeserializeOwned, Serialize};
 use std::fs::File;
 use std::io::{BufRead, BufReader, BufWriter, Write};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::str;
+use std::sync::Mutex;
 
 const DEFAULT_MUTAGEN_DIR: &str = "target/mutagen";
 const DEFAULT_MUTAGEN_FILENAME: &str = "mutations";
 const JSON_MUTAGEN_FILENAME: &str = "mutations.json";
 
 const DEFAULT_COVERAGE_FILENAME: &str = "coverage";
+const JSON_REPORT_FILENAME: &str = "report.json";
+const HTML_REPORT_DIRNAME: &str = "html";
+const JUNIT_REPORT_FILENAME: &str = "junit.xml";
+const SARIF_REPORT_FILENAME: &str = "report.sarif";
+const STRYKER_REPORT_FILENAME: &str = "stryker.json";
+const CACHE_FILENAME: &str = "cache";
+const JOURNAL_FILENAME: &str = "journal";
+const KILL_HISTORY_FILENAME: &str = "kill-history";
+const UNVIABLE_MARKER_FILENAME: &str = "unviable";
+const NEXTEST_BINARIES_FILENAME: &str = "nextest-binaries.json";
+const NEXTEST_CARGO_METADATA_FILENAME: &str = "nextest-cargo-metadata.json";
 
 /// Finds the file that contains the descriptions of all mutations as written by the procedural macro
 pub fn get_mutations_file() -> Result<PathBuf> {
@@ -25,19 +38,103 @@ pub fn get_coverage_file() -> Result<PathBuf> {
     Ok(mutagen_dir()?.join(DEFAULT_COVERAGE_FILENAME))
 }
 
-/// queries `cargo` for the workspace root and locates the directory to write mutagen-specific information
+/// Finds the file where the report of the mutation analysis is written to in json format.
+pub fn get_report_file_json() -> Result<PathBuf> {
+    Ok(mutagen_dir()?.join(JSON_REPORT_FILENAME))
+}
+
+/// Finds the directory where the web-based report is written to.
+pub fn get_report_dir_html() -> Result<PathBuf> {
+    Ok(mutagen_dir()?.join(HTML_REPORT_DIRNAME))
+}
+
+/// Finds the file where the report is written to in JUnit XML format.
+pub fn get_report_file_junit() -> Result<PathBuf> {
+    Ok(mutagen_dir()?.join(JUNIT_REPORT_FILENAME))
+}
+
+/// Finds the file where the report is written to in SARIF format.
+pub fn get_report_file_sarif() -> Result<PathBuf> {
+    Ok(mutagen_dir()?.join(SARIF_REPORT_FILENAME))
+}
+
+/// Finds the file where the report is written to in the mutation-testing-elements format of Stryker.
+pub fn get_report_file_stryker() -> Result<PathBuf> {
+    Ok(mutagen_dir()?.join(STRYKER_REPORT_FILENAME))
+}
+
+/// Finds the file where results of mutants are cached between runs.
+pub fn get_cache_file() -> Result<PathBuf> {
+    Ok(mutagen_dir()?.join(CACHE_FILENAME))
+}
+
+/// Finds the file where the results of the current run are recorded to be able to resume the run.
+pub fn get_journal_file() -> Result<PathBuf> {
+    Ok(mutagen_dir()?.join(JOURNAL_FILENAME))
+}
+
+/// Finds the file where the tests that killed mutants are recorded across runs.
+pub fn get_kill_history_file() -> Result<PathBuf> {
+    Ok(mutagen_dir()?.join(KILL_HISTORY_FILENAME))
+}
+
+/// Finds the coverage file for a single test suite.
+///
+/// Each test suite writes its coverage to its own file so that test suites can be run at the same time.
+pub fn get_coverage_file_for_testsuite(testsuite_id: usize) -> Result<PathBuf> {
+    Ok(mutagen_dir()?.join(format!("{}-{}", DEFAULT_COVERAGE_FILENAME, testsuite_id)))
+}
+
+/// Finds the marker file that is created if the mutant with the given id turns out to be unviable.
+pub fn get_unviable_marker_file(mutation_id: usize) -> Result<PathBuf> {
+    Ok(mutagen_dir()?.join(format!("{}-{}", UNVIABLE_MARKER_FILENAME, mutation_id)))
+}
+
+/// Finds the file where the list of test-binaries built by cargo-nextest is written to.
+pub fn get_nextest_binaries_file() -> Result<PathBuf> {
+    Ok(mutagen_dir()?.join(NEXTEST_BINARIES_FILENAME))
+}
+
+/// Finds the file where the cargo metadata for reusing the build of cargo-nextest is written to.
+pub fn get_nextest_cargo_metadata_file() -> Result<PathBuf> {
+    Ok(mutagen_dir()?.join(NEXTEST_CARGO_METADATA_FILENAME))
+}
+
+lazy_static! {
+    /// cached result of `query_workspace_root`, since `cargo metadata` is slow and its result does not change
+    static ref WORKSPACE_ROOT: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+/// locates the directory to write mutagen-specific information
 fn mutagen_dir() -> Result<PathBuf> {
+    Ok(workspace_root()?.join(DEFAULT_MUTAGEN_DIR))
+}
+
+/// locates the root directory of the workspace.
+///
+/// Paths to source files of mutations are relative to this directory.
+/// The directory is looked up only once per process.
+pub fn workspace_root() -> Result<PathBuf> {
+    let mut workspace_root = WORKSPACE_ROOT.lock().unwrap();
+    if let Some(dir) = &*workspace_root {
+        return Ok(dir.clone());
+    }
+    let dir = query_workspace_root()?;
+    *workspace_root = Some(dir.clone());
+    Ok(dir)
+}
+
+/// queries `cargo` for the workspace root
+fn query_workspace_root() -> Result<PathBuf> {
     let metadata = Command::new("cargo").arg("metadata").output()?;
     if !metadata.status.success() {
         bail!("{}", str::from_utf8(&metadata.stderr)?);
     }
     let meta_json = json::parse(str::from_utf8(&metadata.stdout)?)?;
-    let root_dir = Path::new(
-        meta_json["workspace_root"]
-            .as_str()
-            .with_context(|| "cargo metadata misses workspace_root")?,
-    );
-    Ok(root_dir.join(DEFAULT_MUTAGEN_DIR))
+    let root_dir = meta_json["workspace_root"]
+        .as_str()
+        .with_context(|| "cargo metadata misses workspace_root")?;
+    Ok(PathBuf::from(root_dir))
 }
 
 pub fn read_items<T: DeserializeOwned>(filepath: &Path) -> Result<Vec<T>> {
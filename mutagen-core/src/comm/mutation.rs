@@ -1,6 +1,8 @@
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 
+use anyhow::{Context, Result};
 use proc_macro2::Span;
 use serde::{Deserialize, Serialize};
 
@@ -40,7 +42,7 @@ impl Mutation {
         let fn_name = context.fn_name.clone();
         let start = span.start();
         let end = span.end();
-        let source_file = span.unwrap().source_file().path();
+        let source_file = span.unwrap().local_file().unwrap_or_default();
         let location_in_file = format!(
             "{}:{}-{}:{}",
             start.line, start.column, end.line, end.column
@@ -92,6 +94,39 @@ impl Mutation {
     }
 }
 
+/// Location of a mutation in its source file.
+///
+/// Lines are 1-based and columns are 0-based, counted in characters.
+/// The end of the location is exclusive.
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub struct SourceLocation {
+    pub start_line: usize,
+    pub start_column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl FromStr for SourceLocation {
+    type Err = anyhow::Error;
+
+    /// parses a location in the format `line:column-line:column`, as written by `Mutation::new_spanned`
+    fn from_str(s: &str) -> Result<Self> {
+        let parse_line_column = |s: &str| -> Result<(usize, usize)> {
+            let (line, column) = s.split_once(':').context("missing `:`")?;
+            Ok((line.parse()?, column.parse()?))
+        };
+        let (start, end) = s.split_once('-').context("missing `-`")?;
+        let (start_line, start_column) = parse_line_column(start)?;
+        let (end_line, end_column) = parse_line_column(end)?;
+        Ok(Self {
+            start_line,
+            start_column,
+            end_line,
+            end_column,
+        })
+    }
+}
+
 impl BakedMutation {
     pub fn id(&self) -> usize {
         self.id
@@ -105,6 +140,10 @@ impl BakedMutation {
         self.mutation.mutator.deref()
     }
 
+    pub fn impl_name(&self) -> Option<&str> {
+        self.mutation.impl_name.as_deref()
+    }
+
     pub fn fn_name(&self) -> Option<&str> {
         // TODO: use Option::deref instead
         self.mutation.fn_name.as_deref()
@@ -124,6 +163,12 @@ impl BakedMutation {
     pub fn location_in_file(&self) -> &str {
         self.mutation.location_in_file.deref()
     }
+    /// Parses the location of the mutation in the source file.
+    pub fn location(&self) -> Result<SourceLocation> {
+        self.location_in_file()
+            .parse()
+            .with_context(|| format!("invalid location `{}`", self.location_in_file()))
+    }
     pub fn mutation_description(&self) -> String {
         self.mutation.mutation_description()
     }
@@ -138,7 +183,10 @@ impl AsRef<Mutation> for BakedMutation {
     }
 }
 
-#[cfg(test)]
+/// Stub mutations for the tests of mutagen.
+///
+/// The tests of `mutagen-runner` enable them with the feature `test_stubs`.
+#[cfg(any(test, feature = "test_stubs"))]
 impl Mutation {
     /// Create a new mutation for testing purposes.
     pub fn new_stub() -> Self {
@@ -152,4 +200,74 @@ impl Mutation {
             location_in_file: "stub".to_owned(),
         }
     }
+
+    pub fn stub_impl_name(self, impl_name: &str) -> Self {
+        Self {
+            impl_name: Some(impl_name.to_owned()),
+            ..self
+        }
+    }
+
+    pub fn stub_fn_name(self, fn_name: &str) -> Self {
+        Self {
+            fn_name: Some(fn_name.to_owned()),
+            ..self
+        }
+    }
+
+    pub fn stub_mutator(self, mutator: &str) -> Self {
+        Self {
+            mutator: mutator.to_owned(),
+            ..self
+        }
+    }
+
+    pub fn stub_code(self, original_code: &str, mutated_code: &str) -> Self {
+        Self {
+            original_code: original_code.to_owned(),
+            mutated_code: mutated_code.to_owned(),
+            ..self
+        }
+    }
+
+    pub fn stub_source_file(self, source_file: &str) -> Self {
+        Self {
+            source_file: source_file.into(),
+            ..self
+        }
+    }
+
+    pub fn stub_location(self, location_in_file: &str) -> Self {
+        Self {
+            location_in_file: location_in_file.to_owned(),
+            ..self
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_location_parse() {
+        let location = "12:5-13:16".parse::<SourceLocation>().unwrap();
+
+        assert_eq!(
+            location,
+            SourceLocation {
+                start_line: 12,
+                start_column: 5,
+                end_line: 13,
+                end_column: 16,
+            }
+        );
+    }
+
+    #[test]
+    fn source_location_parse_invalid() {
+        assert!("stub".parse::<SourceLocation>().is_err());
+        assert!("12:5".parse::<SourceLocation>().is_err());
+        assert!("12:5-x:16".parse::<SourceLocation>().is_err());
+    }
 }
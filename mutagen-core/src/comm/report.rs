@@ -7,16 +7,27 @@ use super::BakedMutation;
 #[derive(Serialize, Deserialize, Default)]
 pub struct MutagenReport {
     mutant_results: HashMap<BakedMutation, MutantStatus>,
+    /// details about killed mutants, indexed by mutation-id
+    #[serde(default)]
+    kill_details: HashMap<usize, KillDetails>,
     summary: ReportSummary,
 }
 
-#[derive(Copy, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize)]
 pub struct ReportSummary {
     num_mutations: usize,
     killed: usize,
     timeout: usize,
     survived: usize,
     not_covered: usize,
+    #[serde(default)]
+    skipped: usize,
+    #[serde(default)]
+    unviable: usize,
+    #[serde(default)]
+    ignored: usize,
+    #[serde(default)]
+    limit_exceeded: usize,
 }
 
 impl MutagenReport {
@@ -31,10 +42,19 @@ impl MutagenReport {
         self.summary.add_mutation_result(status);
     }
 
+    /// adds the details about how a mutant has been killed.
+    pub fn add_kill_details(&mut self, mutation_id: usize, details: KillDetails) {
+        self.kill_details.insert(mutation_id, details);
+    }
+
+    pub fn kill_details(&self, mutation_id: usize) -> Option<&KillDetails> {
+        self.kill_details.get(&mutation_id)
+    }
+
     /// creates a map of mutations per file.
     ///
     /// The map gets iterated in alphabetical order of the files and the list of mutations is sorted by mutation-id
-    fn mutations_per_file(
+    pub fn mutations_per_file(
         &self,
     ) -> BTreeMap<&std::path::Path, Vec<(&BakedMutation, MutantStatus)>> {
         let mut map = BTreeMap::new();
@@ -56,6 +76,14 @@ impl MutagenReport {
         let mutations_per_file = self.mutations_per_file().into_iter().collect::<Vec<_>>();
 
         for (file, mutations) in mutations_per_file {
+            // skipped, unviable and ignored mutations are not listed
+            let mutations = mutations
+                .into_iter()
+                .filter(|(_, s)| s.is_tested())
+                .collect::<Vec<_>>();
+            if mutations.is_empty() {
+                continue;
+            }
             let num_mutations = mutations.len();
             // TODO: use mutations.drain_filter
             let survived = mutations
@@ -94,13 +122,47 @@ impl MutagenReport {
         }
     }
 
+    /// prints the unviable mutants, if any
+    pub fn print_unviable(&self) {
+        let unviable = self
+            .mutant_results()
+            .into_iter()
+            .filter(|(_, s)| *s == MutantStatus::Unviable)
+            .collect::<Vec<_>>();
+        if unviable.is_empty() {
+            return;
+        }
+        println!("UNVIABLE");
+        for (m, _) in unviable {
+            println!(
+                "        {}: {} at {}@{}{}",
+                m.id(),
+                m.mutation_description(),
+                m.source_file().display(),
+                m.location_in_file(),
+                m.context_description_in_brackets(),
+            );
+        }
+    }
+
     pub fn summary(&self) -> ReportSummary {
         self.summary
     }
+
+    /// Returns all mutations with their result, sorted by mutation-id.
+    pub fn mutant_results(&self) -> Vec<(&BakedMutation, MutantStatus)> {
+        let mut results = self
+            .mutant_results
+            .iter()
+            .map(|(m, s)| (m, *s))
+            .collect::<Vec<_>>();
+        results.sort_unstable_by_key(|(m, _)| m.id());
+        results
+    }
 }
 
 impl ReportSummary {
-    fn add_mutation_result(&mut self, status: MutantStatus) {
+    pub fn add_mutation_result(&mut self, status: MutantStatus) {
         self.num_mutations += 1;
         match status {
             MutantStatus::NotCovered => {
@@ -113,21 +175,104 @@ impl ReportSummary {
                 self.timeout += 1;
                 self.killed += 1;
             }
+            MutantStatus::LimitExceeded(_) => {
+                self.limit_exceeded += 1;
+                self.killed += 1;
+            }
+            MutantStatus::Skipped => self.skipped += 1,
+            MutantStatus::Unviable => self.unviable += 1,
+            MutantStatus::Ignored => self.ignored += 1,
+        }
+    }
+
+    pub fn num_mutations(&self) -> usize {
+        self.num_mutations
+    }
+    /// number of killed mutants, including the ones killed by timeout
+    pub fn killed(&self) -> usize {
+        self.killed
+    }
+    pub fn timeout(&self) -> usize {
+        self.timeout
+    }
+    /// number of mutants killed by exceeding a resource limit
+    pub fn limit_exceeded(&self) -> usize {
+        self.limit_exceeded
+    }
+    /// number of survived mutants, including the ones not covered by any test
+    pub fn survived(&self) -> usize {
+        self.survived
+    }
+    pub fn not_covered(&self) -> usize {
+        self.not_covered
+    }
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+    pub fn unviable(&self) -> usize {
+        self.unviable
+    }
+    pub fn ignored(&self) -> usize {
+        self.ignored
+    }
+
+    /// number of mutants that have been neither skipped, found to be unviable nor ignored
+    pub fn num_tested(&self) -> usize {
+        self.num_mutations - self.skipped - self.unviable - self.ignored
+    }
+
+    /// percentage of killed mutants of all mutants that have been neither skipped, found to be unviable nor ignored.
+    ///
+    /// The score of an empty report is 100%.
+    pub fn mutation_score(&self) -> f64 {
+        if self.num_tested() == 0 {
+            100.0
+        } else {
+            compute_percent(self.num_tested(), self.killed)
+        }
+    }
+
+    /// percentage of killed mutants of all mutants that have been covered by the tests and count towards the score.
+    ///
+    /// The score is 100%, if no mutant has been covered.
+    pub fn mutation_score_covered(&self) -> f64 {
+        let num_covered = self.num_tested() - self.not_covered;
+        if num_covered == 0 {
+            100.0
+        } else {
+            compute_percent(num_covered, self.killed)
         }
     }
 
     pub fn print(&self) {
-        let percent_mutations_killed = compute_percent(self.num_mutations, self.killed);
-        let percent_mutations_timeout = compute_percent(self.num_mutations, self.timeout);
-        let percent_mutations_survived = compute_percent(self.num_mutations, self.survived);
-        let percent_mutations_not_covered = compute_percent(self.num_mutations, self.not_covered);
+        let num_tested = self.num_tested();
+        let percent_mutations_killed = compute_percent(num_tested, self.killed);
+        let percent_mutations_timeout = compute_percent(num_tested, self.timeout);
+        let percent_mutations_survived = compute_percent(num_tested, self.survived);
+        let percent_mutations_not_covered = compute_percent(num_tested, self.not_covered);
 
         println!();
         println!("{} generated mutants", self.num_mutations);
+        if self.skipped > 0 {
+            println!("{} mutants skipped", self.skipped);
+        }
+        if self.unviable > 0 {
+            println!("{} mutants unviable", self.unviable);
+        }
+        if self.ignored > 0 {
+            println!("{} mutants ignored", self.ignored);
+        }
         println!(
             "{}({:.2}%) mutants killed, {}({:.2}%) by timeout",
             self.killed, percent_mutations_killed, self.timeout, percent_mutations_timeout,
         );
+        if self.limit_exceeded > 0 {
+            println!(
+                "{}({:.2}%) mutants killed by exceeding a resource limit",
+                self.limit_exceeded,
+                compute_percent(num_tested, self.limit_exceeded),
+            );
+        }
         println!(
             "{}({:.2}%) mutants SURVIVED, {}({:.2}%) NOT COVERED",
             self.survived,
@@ -138,6 +283,15 @@ impl ReportSummary {
     }
 }
 
+/// Details about how a mutant has been killed, taken from the output of the test suite
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+pub struct KillDetails {
+    /// names of the tests that failed
+    pub failed_tests: Vec<String>,
+    /// the first panic message of the failed tests, possibly truncated
+    pub panic_message: Option<String>,
+}
+
 /// Result from a test run
 #[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
 pub enum MutantStatus {
@@ -149,12 +303,34 @@ pub enum MutantStatus {
     Killed(Option<i32>),
     /// the test timed out
     Timeout,
+    /// the test exceeded a limit of its resources
+    LimitExceeded(ResourceLimit),
+    /// the mutation was not selected to be tested
+    Skipped,
+    /// an optimistic assumption of the mutator failed, so the mutation could not be written in real source code
+    Unviable,
+    /// the mutant is listed as equivalent in the suppression file
+    Ignored,
 }
 
 impl MutantStatus {
-    fn survived(self) -> bool {
+    /// Returns true if the mutant survived, including not covered mutants.
+    pub fn survived(self) -> bool {
         self == Self::NotCovered || self == Self::Survived
     }
+
+    /// Returns true if the mutant has been killed, including by a timeout or an exceeded resource limit.
+    pub fn killed(self) -> bool {
+        matches!(
+            self,
+            Self::Killed(_) | Self::Timeout | Self::LimitExceeded(_)
+        )
+    }
+
+    /// Returns true if the mutant counts towards the mutation score.
+    pub fn is_tested(self) -> bool {
+        !matches!(self, Self::Skipped | Self::Unviable | Self::Ignored)
+    }
 }
 
 impl fmt::Display for MutantStatus {
@@ -164,10 +340,70 @@ impl fmt::Display for MutantStatus {
             Self::Survived => write!(f, "SURVIVED"),
             Self::Killed(_) => write!(f, "killed"),
             Self::Timeout => write!(f, "killed (timeout)"),
+            Self::LimitExceeded(limit) => write!(f, "killed ({} limit exceeded)", limit),
+            Self::Skipped => write!(f, "skipped"),
+            Self::Unviable => write!(f, "unviable"),
+            Self::Ignored => write!(f, "ignored"),
+        }
+    }
+}
+
+/// A resource of the test suite that can be limited
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceLimit {
+    /// the address space of a process
+    Memory,
+    /// the CPU time of a process
+    Cpu,
+    /// the number of processes and threads
+    Processes,
+    /// the size of written files
+    FileSize,
+}
+
+impl fmt::Display for ResourceLimit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Memory => write!(f, "memory"),
+            Self::Cpu => write!(f, "CPU time"),
+            Self::Processes => write!(f, "process"),
+            Self::FileSize => write!(f, "file size"),
         }
     }
 }
 
 fn compute_percent(total: usize, num: usize) -> f64 {
-    100.0 * num as f64 / total as f64
+    if total == 0 {
+        0.0
+    } else {
+        100.0 * num as f64 / total as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comm::Mutation;
+
+    #[test]
+    fn mutation_score_without_skipped_unviable_and_ignored() {
+        let mut report = MutagenReport::new();
+        report.add_mutation_result(
+            Mutation::new_stub().with_id(1, 1),
+            MutantStatus::Killed(Some(101)),
+        );
+        report.add_mutation_result(Mutation::new_stub().with_id(2, 2), MutantStatus::Survived);
+        report.add_mutation_result(Mutation::new_stub().with_id(3, 3), MutantStatus::Skipped);
+        report.add_mutation_result(Mutation::new_stub().with_id(4, 4), MutantStatus::Unviable);
+        report.add_mutation_result(Mutation::new_stub().with_id(5, 5), MutantStatus::Ignored);
+
+        let summary = report.summary();
+
+        assert_eq!(summary.num_mutations(), 5);
+        assert_eq!(summary.unviable(), 1);
+        assert_eq!(summary.ignored(), 1);
+        assert_eq!(summary.num_tested(), 2);
+        assert_eq!(summary.mutation_score(), 50.0);
+    }
 }
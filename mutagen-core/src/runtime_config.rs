@@ -13,12 +13,24 @@
 //! * `MUTAGEN_MODE=mutation`: activate a single mutation (default)
 //! * `MUTAGEN_MODE=coverage`: perform coverage analysis
 //!
-//! In the mode `coverage`, it is required to add the environment variable `MUTAGEN_NUM_MUTATIONS=N` where `N` are the total number of mutations
+//! In the mode `coverage`, it is required to add the environment variable `MUTAGEN_NUM_MUTATIONS=N` where `N` are the total number of mutations.
+//! The variable `MUTAGEN_COVERAGE_FILE` optionally sets the file where coverage hits are appended to.
+//! By default, the file `target/mutagen/coverage` is used.
+//! Several processes can write to the same file, e.g. when doctests are run.
+//!
+//! If the optimistic assumption of a mutator fails, the mutant is unviable and the program panics.
+//! The variable `MUTAGEN_UNVIABLE_FILE` optionally sets a marker file that is created before panicking,
+//! which tells the runner that the mutant is unviable instead of killed.
+//!
+//! Coverage hits are recorded per test: each test of the default test harness runs in a thread named after the test.
 
 use lazy_static::lazy_static;
-use std::fs::File;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fs::{File, OpenOptions};
 use std::io::{BufWriter, Write};
 use std::ops::Deref;
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::RwLock;
 
@@ -44,7 +56,12 @@ pub enum MutagenRuntimeConfig {
     Coverage(CoverageRecorder),
 }
 
-/// Counts how many times each mutator has been covered and reports when a mutator is covered the first time.
+thread_local! {
+    /// the mutators that have been covered by the current thread
+    static COVERED_IN_THREAD: RefCell<HashSet<usize>> = RefCell::new(HashSet::new());
+}
+
+/// Counts how many times each mutator has been covered and reports when a mutator is covered the first time by each test.
 pub struct CoverageRecorder {
     coverage: CoverageHitCollector,
     coverage_file: File,
@@ -102,8 +119,12 @@ impl MutagenRuntimeConfig {
 
     /// Function to abort the computation in case a optimistic mutation fails.
     ///
-    /// In the future, this will be configurable
+    /// The marker file given by `MUTAGEN_UNVIABLE_FILE` is created before panicking.
     pub fn optimistic_assumption_failed(&self) -> ! {
+        if let Some(unviable_file) = std::env::var_os("MUTAGEN_UNVIABLE_FILE") {
+            // the panic is reported anyway, even if the marker cannot be written
+            let _ = File::create(unviable_file);
+        }
         match self {
             Self::Mutation(m_id) => {
                 panic!("optimistic assumption failed for mutation {}", m_id);
@@ -143,8 +164,13 @@ impl MutagenRuntimeConfig {
 impl CoverageRecorder {
     fn new(num_mutations: usize) -> Self {
         let coverage = CoverageHitCollector::new(num_mutations);
-        let coverage_filepath = comm::get_coverage_file().unwrap();
-        let coverage_file = File::create(&coverage_filepath)
+        let coverage_filepath = std::env::var_os("MUTAGEN_COVERAGE_FILE")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| comm::get_coverage_file().unwrap());
+        let coverage_file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&coverage_filepath)
             .unwrap_or_else(|_| panic!("unable to open file {:?}", &coverage_filepath));
 
         Self {
@@ -154,9 +180,16 @@ impl CoverageRecorder {
     }
 
     fn covered(&self, mutator_id: usize) {
-        // report first coverage
-        if self.coverage.hit(mutator_id) {
-            let coverage_hit = comm::CoverageHit { mutator_id };
+        let first_hit = self.coverage.hit(mutator_id);
+        let first_hit_in_thread = COVERED_IN_THREAD.with(|c| c.borrow_mut().insert(mutator_id));
+
+        // report first coverage of each test
+        if first_hit || first_hit_in_thread {
+            let test_name = std::thread::current().name().map(|name| name.to_owned());
+            let coverage_hit = comm::CoverageHit {
+                mutator_id,
+                test_name,
+            };
 
             let mut w = BufWriter::new(&self.coverage_file);
             serde_json::to_writer(&mut w, &coverage_hit).expect("unable to write to coverage file");
@@ -0,0 +1,208 @@
+//! Comparison of the results of a run with a saved report of an earlier run.
+//!
+//! Mutation ids change whenever code is added or removed, so mutations are matched by a stable key instead:
+//! the source file, the surrounding `impl`-block and function, the mutator and the original and mutated code.
+//! Mutations with the same key are distinguished by their order in the source file.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use mutagen_core::comm::{JsonMutantStatus, JsonMutationResult, JsonReport};
+
+/// Key of a mutation that does not change when unrelated code is changed
+#[derive(Debug, PartialEq, Eq, Hash, Copy, Clone)]
+struct StableKey<'a> {
+    source_file: &'a Path,
+    impl_name: Option<&'a str>,
+    fn_name: Option<&'a str>,
+    mutator: &'a str,
+    original_code: &'a str,
+    mutated_code: &'a str,
+    /// index of the mutation among all mutations with the same key
+    occurrence: usize,
+}
+
+/// Differences between the results of a baseline report and the current report
+#[derive(Debug)]
+pub struct BaselineComparison<'a> {
+    /// mutants that survived in the current run but have been killed in the baseline or are new
+    pub newly_survived: Vec<&'a JsonMutationResult>,
+    /// mutants that have been killed in the current run but survived in the baseline
+    pub newly_killed: Vec<&'a JsonMutationResult>,
+    /// mutations that are not contained in the baseline
+    pub added: Vec<&'a JsonMutationResult>,
+    /// mutations of the baseline that are not contained in the current report
+    pub removed: Vec<&'a JsonMutationResult>,
+}
+
+impl<'a> BaselineComparison<'a> {
+    /// Compares the current report with the baseline.
+    ///
+    /// Skipped mutants are neither reported as newly survived nor as newly killed.
+    pub fn new(baseline: &'a JsonReport, current: &'a JsonReport) -> Self {
+        let mut baseline_results = stable_keys(&baseline.mutations);
+
+        let mut comparison = Self {
+            newly_survived: vec![],
+            newly_killed: vec![],
+            added: vec![],
+            removed: vec![],
+        };
+        for (key, result) in stable_keys(&current.mutations) {
+            let status = result.status;
+            match baseline_results.remove(&key) {
+                Some(baseline_result) => {
+                    let baseline_status = baseline_result.status;
+                    if survived(status) && killed(baseline_status) {
+                        comparison.newly_survived.push(result);
+                    } else if killed(status) && survived(baseline_status) {
+                        comparison.newly_killed.push(result);
+                    }
+                }
+                None => {
+                    if survived(status) {
+                        comparison.newly_survived.push(result);
+                    }
+                    comparison.added.push(result);
+                }
+            }
+        }
+        comparison.removed = baseline_results.into_values().collect();
+
+        comparison.newly_survived.sort_unstable_by_key(|r| r.id);
+        comparison.newly_killed.sort_unstable_by_key(|r| r.id);
+        comparison.added.sort_unstable_by_key(|r| r.id);
+        comparison.removed.sort_unstable_by_key(|r| r.id);
+        comparison
+    }
+
+    pub fn print(&self) {
+        println!();
+        println!("COMPARISON WITH BASELINE");
+        print_results("newly SURVIVED", &self.newly_survived);
+        print_results("newly killed", &self.newly_killed);
+        print_results("added", &self.added);
+        print_results("removed", &self.removed);
+    }
+}
+
+fn print_results(title: &str, results: &[&JsonMutationResult]) {
+    println!("    {} {} mutants", results.len(), title);
+    for r in results {
+        println!(
+            "        {}@{}: {} ({})",
+            r.source_file.display(),
+            r.location,
+            r.description,
+            r.mutant_status()
+        );
+    }
+}
+
+/// assigns the stable key to each mutation
+fn stable_keys(results: &[JsonMutationResult]) -> HashMap<StableKey<'_>, &JsonMutationResult> {
+    let mut results = results.iter().collect::<Vec<_>>();
+    // mutations with the same key are numbered in the order of their ids
+    results.sort_unstable_by_key(|r| r.id);
+
+    let mut occurrences = HashMap::new();
+    results
+        .into_iter()
+        .map(|r| {
+            let key = StableKey {
+                source_file: &r.source_file,
+                impl_name: r.impl_name.as_deref(),
+                fn_name: r.fn_name.as_deref(),
+                mutator: &r.mutator,
+                original_code: &r.original_code,
+                mutated_code: &r.mutated_code,
+                occurrence: 0,
+            };
+            let occurrence = occurrences.entry(key).or_insert(0);
+            let key = StableKey {
+                occurrence: *occurrence,
+                ..key
+            };
+            *occurrence += 1;
+            (key, r)
+        })
+        .collect()
+}
+
+fn survived(status: JsonMutantStatus) -> bool {
+    status == JsonMutantStatus::Survived || status == JsonMutantStatus::NotCovered
+}
+
+fn killed(status: JsonMutantStatus) -> bool {
+    matches!(
+        status,
+        JsonMutantStatus::Killed | JsonMutantStatus::Timeout | JsonMutantStatus::LimitExceeded
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mutagen_core::comm::{MutagenReport, MutantStatus, Mutation};
+
+    /// creates a report with mutations of the given ids, original code and status.
+    fn json_report(results: &[(usize, &str, MutantStatus)]) -> JsonReport {
+        let mut report = MutagenReport::new();
+        for (id, original_code, status) in results {
+            let mutation = Mutation::new_stub()
+                .stub_source_file("src/lib.rs")
+                .stub_fn_name("foo")
+                .stub_mutator("lit_int")
+                .stub_code(original_code, "0")
+                .stub_location(&format!("{}:0-{}:1", id, id))
+                .with_id(*id, *id);
+            report.add_mutation_result(mutation, *status);
+        }
+        JsonReport::new(&report)
+    }
+
+    fn ids(results: &[&JsonMutationResult]) -> Vec<usize> {
+        results.iter().map(|r| r.id).collect()
+    }
+
+    #[test]
+    fn compare_with_shifted_ids() {
+        let baseline = json_report(&[
+            (1, "1", MutantStatus::Killed(Some(101))),
+            (2, "2", MutantStatus::Survived),
+            (3, "3", MutantStatus::Killed(Some(101))),
+        ]);
+        let current = json_report(&[
+            (1, "5", MutantStatus::Killed(Some(101))),
+            (2, "1", MutantStatus::Killed(Some(101))),
+            (3, "2", MutantStatus::Killed(Some(101))),
+        ]);
+
+        let comparison = BaselineComparison::new(&baseline, &current);
+
+        assert!(comparison.newly_survived.is_empty());
+        assert_eq!(ids(&comparison.newly_killed), vec![3]);
+        assert_eq!(ids(&comparison.added), vec![1]);
+        assert_eq!(ids(&comparison.removed), vec![3]);
+    }
+
+    #[test]
+    fn new_survivors() {
+        let baseline = json_report(&[
+            (1, "1", MutantStatus::Killed(Some(101))),
+            (2, "1", MutantStatus::Timeout),
+        ]);
+        let current = json_report(&[
+            (1, "1", MutantStatus::Killed(Some(101))),
+            (2, "1", MutantStatus::NotCovered),
+            (3, "3", MutantStatus::Survived),
+            (4, "4", MutantStatus::Skipped),
+        ]);
+
+        let comparison = BaselineComparison::new(&baseline, &current);
+
+        assert_eq!(ids(&comparison.newly_survived), vec![2, 3]);
+        assert_eq!(ids(&comparison.added), vec![3, 4]);
+        assert!(comparison.removed.is_empty());
+    }
+}
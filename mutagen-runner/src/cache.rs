@@ -0,0 +1,304 @@
+//! Persistent cache of the results of mutants.
+//!
+//! The result of a mutant can be reused if neither the mutation, the test binaries nor the settings of the run have changed.
+//! Each result is stored under a key that is computed from the mutator, the original and mutated code,
+//! the content of the source file of the mutation, the content of all test binaries
+//! and the settings that influence the result, e.g. the timeout policy and the resource limits.
+//!
+//! The cache is stored in `target/mutagen/cache`.
+//! Runs that only test some of the mutants keep the results of all other mutants.
+//! Results that have not been used for 30 days are removed at the end of a run.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use mutagen_core::comm::{self, BakedMutation, KillDetails, MutantStatus};
+
+use super::test_bin::hash_test_bins;
+use super::TestBinTested;
+
+/// time in seconds after which unused results are removed from the cache
+const MAX_AGE: u64 = 30 * 24 * 60 * 60;
+
+/// A single entry in the cache file
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    key: String,
+    status: MutantStatus,
+    #[serde(default)]
+    kill_details: Option<KillDetails>,
+    /// time of the last run that used this result, in seconds since the unix epoch
+    #[serde(default)]
+    last_used: u64,
+}
+
+/// cached result of a single mutant
+type CachedResult = (MutantStatus, Option<KillDetails>);
+
+pub struct MutationCache {
+    cache_path: PathBuf,
+    cache_file: File,
+    /// results read from the cache file or added during this run, by their key
+    entries: HashMap<String, CacheEntry>,
+    workspace_root: PathBuf,
+    test_bins_hash: String,
+    /// description of the settings of the run that influence the results
+    settings: String,
+    source_hashes: HashMap<PathBuf, String>,
+    /// start of this run in seconds since the unix epoch
+    now: u64,
+}
+
+impl MutationCache {
+    /// Reads the cache from `target/mutagen/cache` for the given test binaries and settings.
+    ///
+    /// The settings describe everything besides the test binaries that influences the results of the mutants,
+    /// e.g. the timeout policy, the resource limits and the test runner.
+    pub fn open(test_bins: &[TestBinTested], settings: String) -> Result<Self> {
+        Self::open_at(
+            comm::get_cache_file()?,
+            comm::workspace_root()?,
+            hash_test_bins(test_bins)?,
+            settings,
+        )
+    }
+
+    fn open_at(
+        cache_path: PathBuf,
+        workspace_root: PathBuf,
+        test_bins_hash: String,
+        settings: String,
+    ) -> Result<Self> {
+        let entries = if cache_path.exists() {
+            // an unreadable cache is ignored
+            comm::read_items::<CacheEntry>(&cache_path)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|e| (e.key.clone(), e))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+        let cache_file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&cache_path)?;
+
+        Ok(Self {
+            cache_path,
+            cache_file,
+            entries,
+            workspace_root,
+            test_bins_hash,
+            settings,
+            source_hashes: HashMap::new(),
+            now: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        })
+    }
+
+    /// Returns the result of an earlier run of the given mutation, if nothing has changed since.
+    pub fn get(&mut self, m: &BakedMutation) -> Result<Option<CachedResult>> {
+        let key = self.key(m)?;
+        let now = self.now;
+        Ok(self.entries.get_mut(&key).map(|entry| {
+            entry.last_used = now;
+            (entry.status, entry.kill_details.clone())
+        }))
+    }
+
+    /// Adds the result of a mutation to the cache, replacing an earlier result with the same key.
+    pub fn insert(
+        &mut self,
+        m: &BakedMutation,
+        status: MutantStatus,
+        kill_details: Option<&KillDetails>,
+    ) -> Result<()> {
+        let entry = CacheEntry {
+            key: self.key(m)?,
+            status,
+            kill_details: kill_details.cloned(),
+            last_used: self.now,
+        };
+        comm::append_item(&mut self.cache_file, &entry)?;
+        self.entries.insert(entry.key.clone(), entry);
+        Ok(())
+    }
+
+    /// Rewrites the cache file with one result per key, without the results that have not been used recently.
+    pub fn finish(self) -> Result<()> {
+        drop(self.cache_file);
+        let mut cache_file = File::create(&self.cache_path)?;
+        for entry in self.entries.values() {
+            if self.now.saturating_sub(entry.last_used) <= MAX_AGE {
+                comm::append_item(&mut cache_file, entry)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn key(&mut self, m: &BakedMutation) -> Result<String> {
+        let source_file = m.source_file().to_owned();
+        let source_hash = match self.source_hashes.get(&source_file) {
+            Some(hash) => hash.clone(),
+            None => {
+                let source = fs::read(self.workspace_root.join(&source_file)).unwrap_or_default();
+                let hash = format!("{:x}", Sha256::digest(&source));
+                self.source_hashes.insert(source_file, hash.clone());
+                hash
+            }
+        };
+
+        let mut hasher = Sha256::new();
+        for part in &[
+            m.mutator_name(),
+            m.original_code(),
+            m.mutated_code(),
+            m.location_in_file(),
+            &source_hash,
+            &self.test_bins_hash,
+            &self.settings,
+        ] {
+            hasher.update(part.as_bytes());
+            // separate the parts to avoid ambiguities
+            hasher.update([0]);
+        }
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::mutation;
+    use std::path::Path;
+
+    /// creates an empty workspace in the temporary directory containing `src/lib.rs`
+    fn workspace(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("mutagen-cache-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("src/lib.rs"), "fn foo() -> u8 { 1 }\n").unwrap();
+        dir
+    }
+
+    fn open(workspace: &Path, test_bins_hash: &str) -> MutationCache {
+        MutationCache::open_at(
+            workspace.join("cache"),
+            workspace.to_owned(),
+            test_bins_hash.to_owned(),
+            "settings".to_owned(),
+        )
+        .unwrap()
+    }
+
+    const KILLED: MutantStatus = MutantStatus::Killed(Some(101));
+
+    #[test]
+    fn cache_hit() {
+        let workspace = workspace("hit");
+        let mut cache = open(&workspace, "bins");
+        assert_eq!(cache.get(&mutation(1)).unwrap(), None);
+        cache.insert(&mutation(1), KILLED, None).unwrap();
+        cache.finish().unwrap();
+
+        let mut cache = open(&workspace, "bins");
+        assert_eq!(cache.get(&mutation(1)).unwrap(), Some((KILLED, None)));
+        assert_eq!(cache.get(&mutation(2)).unwrap(), None);
+    }
+
+    #[test]
+    fn cache_miss_after_source_change() {
+        let workspace = workspace("source");
+        let mut cache = open(&workspace, "bins");
+        cache.insert(&mutation(1), KILLED, None).unwrap();
+        cache.finish().unwrap();
+
+        fs::write(workspace.join("src/lib.rs"), "fn foo() -> u8 { 1 + 0 }\n").unwrap();
+        let mut cache = open(&workspace, "bins");
+        assert_eq!(cache.get(&mutation(1)).unwrap(), None);
+    }
+
+    #[test]
+    fn cache_miss_after_test_bins_change() {
+        let workspace = workspace("bins");
+        let mut cache = open(&workspace, "bins");
+        cache.insert(&mutation(1), KILLED, None).unwrap();
+        cache.finish().unwrap();
+
+        let mut cache = open(&workspace, "other bins");
+        assert_eq!(cache.get(&mutation(1)).unwrap(), None);
+    }
+
+    #[test]
+    fn cache_miss_after_settings_change() {
+        let workspace = workspace("settings");
+        let mut cache = open(&workspace, "bins");
+        cache
+            .insert(&mutation(1), MutantStatus::Timeout, None)
+            .unwrap();
+        cache.finish().unwrap();
+
+        // e.g. a larger timeout after a run with many jobs
+        let mut cache = MutationCache::open_at(
+            workspace.join("cache"),
+            workspace.clone(),
+            "bins".to_owned(),
+            "other settings".to_owned(),
+        )
+        .unwrap();
+        assert_eq!(cache.get(&mutation(1)).unwrap(), None);
+    }
+
+    #[test]
+    fn keep_results_of_untested_mutants() {
+        let workspace = workspace("partial");
+        let mut cache = open(&workspace, "bins");
+        cache.insert(&mutation(1), KILLED, None).unwrap();
+        cache
+            .insert(&mutation(2), MutantStatus::Survived, None)
+            .unwrap();
+        cache.finish().unwrap();
+
+        // a partial run, e.g. of a single shard, retests only one mutant
+        let mut cache = open(&workspace, "bins");
+        cache.insert(&mutation(2), KILLED, None).unwrap();
+        cache.finish().unwrap();
+
+        let mut cache = open(&workspace, "bins");
+        assert_eq!(cache.get(&mutation(1)).unwrap(), Some((KILLED, None)));
+        assert_eq!(cache.get(&mutation(2)).unwrap(), Some((KILLED, None)));
+        cache.finish().unwrap();
+
+        // an interrupted run does not rewrite the cache, but keeps the appended results
+        let mut cache = open(&workspace, "bins");
+        cache.insert(&mutation(3), KILLED, None).unwrap();
+        drop(cache);
+        let mut cache = open(&workspace, "bins");
+        assert_eq!(cache.get(&mutation(1)).unwrap(), Some((KILLED, None)));
+        assert_eq!(cache.get(&mutation(3)).unwrap(), Some((KILLED, None)));
+    }
+
+    #[test]
+    fn remove_results_unused_for_long() {
+        let workspace = workspace("age");
+        let mut cache = open(&workspace, "bins");
+        cache.now -= MAX_AGE + 1;
+        cache.insert(&mutation(1), KILLED, None).unwrap();
+        cache.insert(&mutation(2), KILLED, None).unwrap();
+        cache.finish().unwrap();
+
+        let mut cache = open(&workspace, "bins");
+        assert!(cache.get(&mutation(2)).unwrap().is_some());
+        cache.finish().unwrap();
+
+        let mut cache = open(&workspace, "bins");
+        assert_eq!(cache.get(&mutation(1)).unwrap(), None);
+        assert!(cache.get(&mutation(2)).unwrap().is_some());
+    }
+}
@@ -0,0 +1,249 @@
+//! Configuration of `cargo-mutagen` via the file `mutagen.toml` in the workspace root.
+//!
+//! Alternatively, the configuration can be given in the section `[package.metadata.mutagen]` of the `Cargo.toml` of the current package.
+//! All settings are optional. Options given on the command line take precedence over the configuration file.
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::str;
+
+use mutagen_core::comm;
+
+use super::limits::LimitConfig;
+use super::nextest::TestRunner;
+use super::report::ReportFormat;
+use super::timeout::TimeoutConfig;
+
+const CONFIG_FILE: &str = "mutagen.toml";
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// additional report formats to write
+    pub report: Vec<ReportFormat>,
+    pub build: BuildConfig,
+    pub exclude: ExcludeConfig,
+    pub score: ScoreConfig,
+    pub timeout: TimeoutConfig,
+    pub limits: LimitConfig,
+}
+
+/// Options for building the test suites
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct BuildConfig {
+    /// features to activate
+    pub features: Vec<String>,
+    pub all_features: bool,
+    /// package to run tests for
+    pub package: Option<String>,
+    /// test all packages in the workspace
+    pub workspace: bool,
+    /// backend that runs the tests, `libtest` or `nextest`
+    pub runner: Option<TestRunner>,
+}
+
+/// Mutations that are never tested and reported as skipped
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ExcludeConfig {
+    /// glob patterns of source files relative to the workspace root
+    pub files: Vec<String>,
+    /// glob patterns of function names
+    pub fns: Vec<String>,
+    /// names of disabled mutators, e.g. `binop_num`
+    pub mutators: Vec<String>,
+}
+
+/// Thresholds for the mutation score
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScoreConfig {
+    /// minimal mutation score of the whole run in percent
+    pub min: Option<f64>,
+    /// exclude mutants that are not covered by any test from the score
+    pub ignore_not_covered: bool,
+    /// minimal mutation score per source file, indexed by a glob pattern relative to the workspace root
+    pub files: BTreeMap<String, f64>,
+}
+
+impl Config {
+    /// Reads the configuration from `mutagen.toml` in the workspace root.
+    ///
+    /// If this file does not exist, the section `[package.metadata.mutagen]` of the manifest of the current package is read.
+    /// If neither exists, the default configuration is returned.
+    pub fn load() -> Result<Self> {
+        let config_path = comm::workspace_root()?.join(CONFIG_FILE);
+        if config_path.exists() {
+            let config = fs::read_to_string(&config_path)?;
+            return Self::parse(&config)
+                .with_context(|| format!("invalid {}", config_path.display()));
+        }
+
+        let manifest_path = locate_manifest()?;
+        let manifest = fs::read_to_string(&manifest_path)?;
+        let config = Self::parse_manifest(&manifest).with_context(|| {
+            format!(
+                "invalid [package.metadata.mutagen] in {}",
+                manifest_path.display()
+            )
+        })?;
+        Ok(config.unwrap_or_default())
+    }
+
+    fn parse(config: &str) -> Result<Self> {
+        Ok(toml::from_str(config)?)
+    }
+
+    /// reads the section `[package.metadata.mutagen]` of a manifest, if present
+    fn parse_manifest(manifest: &str) -> Result<Option<Self>> {
+        let manifest: toml::Value = toml::from_str(manifest)?;
+        let config = manifest
+            .get("package")
+            .and_then(|p| p.get("metadata"))
+            .and_then(|m| m.get("mutagen"));
+        Ok(config.cloned().map(toml::Value::try_into).transpose()?)
+    }
+}
+
+/// value of a flag given on the command line, where `disabled` is its `--no-` variant.
+///
+/// Returns `None` if neither is given, so that the configuration applies.
+pub fn cli_flag(enabled: bool, disabled: bool) -> Option<bool> {
+    if disabled {
+        Some(false)
+    } else if enabled {
+        Some(true)
+    } else {
+        None
+    }
+}
+
+/// queries `cargo` for the manifest of the package in the current directory
+fn locate_manifest() -> Result<PathBuf> {
+    let output = Command::new("cargo")
+        .args(["locate-project", "--message-format", "plain"])
+        .output()?;
+    if !output.status.success() {
+        bail!("{}", str::from_utf8(&output.stderr)?);
+    }
+    Ok(PathBuf::from(str::from_utf8(&output.stdout)?.trim_end()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_empty_config() {
+        let config = Config::parse("").unwrap();
+
+        assert_eq!(config.score.min, None);
+        assert!(!config.score.ignore_not_covered);
+        assert!(config.score.files.is_empty());
+    }
+
+    #[test]
+    fn parse_score_config() {
+        let config = Config::parse(
+            r#"
+            [score]
+            min = 80
+            ignore_not_covered = true
+
+            [score.files]
+            "src/parser/*.rs" = 90.5
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.score.min, Some(80.0));
+        assert!(config.score.ignore_not_covered);
+        assert_eq!(config.score.files["src/parser/*.rs"], 90.5);
+    }
+
+    #[test]
+    fn parse_timeout_config() {
+        let config = Config::parse(
+            r#"
+            [timeout]
+            multiplier = 3
+            slack = "1s"
+            max = "2m"
+            baseline_runs = 5
+            retry = true
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.timeout.multiplier, Some(3.0));
+        assert_eq!(config.timeout.slack.as_deref(), Some("1s"));
+        assert_eq!(config.timeout.max.as_deref(), Some("2m"));
+        assert_eq!(config.timeout.baseline_runs, Some(5));
+        assert!(config.timeout.retry);
+    }
+
+    #[test]
+    fn parse_build_and_exclude_config() {
+        let config = Config::parse(
+            r#"
+            report = ["html", "junit"]
+
+            [build]
+            features = ["foo", "bar"]
+            package = "baz"
+            runner = "nextest"
+
+            [exclude]
+            files = ["src/generated/*.rs"]
+            fns = ["fmt"]
+            mutators = ["lit_str"]
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.report, vec![ReportFormat::Html, ReportFormat::Junit]);
+        assert_eq!(config.build.features, vec!["foo", "bar"]);
+        assert!(!config.build.all_features);
+        assert_eq!(config.build.package.as_deref(), Some("baz"));
+        assert_eq!(config.build.runner, Some(TestRunner::Nextest));
+        assert_eq!(config.exclude.files, vec!["src/generated/*.rs"]);
+        assert_eq!(config.exclude.fns, vec!["fmt"]);
+        assert_eq!(config.exclude.mutators, vec!["lit_str"]);
+    }
+
+    #[test]
+    fn parse_package_metadata() {
+        let config = Config::parse_manifest(
+            r#"
+            [package]
+            name = "foo"
+
+            [package.metadata.mutagen.score]
+            min = 75
+            "#,
+        )
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(config.score.min, Some(75.0));
+        assert!(Config::parse_manifest("[package]\nname = \"foo\"")
+            .unwrap()
+            .is_none());
+        assert!(Config::parse_manifest("[package.metadata.mutagen]\nmin = 1").is_err());
+    }
+
+    #[test]
+    fn parse_unknown_report_format() {
+        assert!(Config::parse("report = [\"pdf\"]").is_err());
+    }
+
+    #[test]
+    fn parse_unknown_field() {
+        assert!(Config::parse("[score]\nmin_score = 80").is_err());
+    }
+}
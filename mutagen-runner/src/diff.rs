@@ -0,0 +1,226 @@
+//! Display of survived mutants as unified diffs of their source file.
+//!
+//! The mutation is applied textually to the current source file at the location of the mutation.
+//! If the source at this location does not match the original code, e.g. because the file has changed since the compilation,
+//! the mutation cannot be shown as diff.
+
+use anyhow::Result;
+use console::style;
+
+use mutagen_core::comm::{self, BakedMutation, MutagenReport};
+
+use super::report::read_source;
+
+/// number of unchanged lines shown before and after the mutation
+const CONTEXT_LINES: usize = 3;
+
+/// Prints each survived mutant of the report as a coloured unified diff.
+pub fn print_survived_diffs(report: &MutagenReport) -> Result<()> {
+    let workspace_root = comm::workspace_root()?;
+
+    println!();
+    println!("SURVIVED MUTANTS AS DIFF");
+    for (file, mutations) in report.mutations_per_file() {
+        let survived = mutations
+            .into_iter()
+            .filter(|(_, s)| s.survived())
+            .collect::<Vec<_>>();
+        if survived.is_empty() {
+            continue;
+        }
+        let source = read_source(&workspace_root, file);
+        for (m, s) in survived {
+            println!();
+            println!(
+                "{}",
+                style(format!(
+                    "{}: {}{} {}",
+                    m.id(),
+                    m.mutation_description(),
+                    m.context_description_in_brackets(),
+                    s
+                ))
+                .bold()
+            );
+            match source.as_deref().and_then(|source| unified_diff(source, m)) {
+                Some(diff) => {
+                    for line in diff.lines() {
+                        let line = match line.chars().next() {
+                            Some('-') => style(line).red(),
+                            Some('+') => style(line).green(),
+                            Some('@') => style(line).cyan(),
+                            _ => style(line),
+                        };
+                        println!("{}", line);
+                    }
+                }
+                None => println!(
+                    "    unable to find the mutation in the source at {}@{}",
+                    file.display(),
+                    m.location_in_file()
+                ),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// creates a unified diff of the source file with the mutation applied.
+///
+/// Returns `None` if the location of the mutation does not match the source.
+fn unified_diff(source: &str, m: &BakedMutation) -> Option<String> {
+    let location = m.location().ok()?;
+    let lines = source.lines().collect::<Vec<_>>();
+    if location.start_line == 0
+        || location.start_line > location.end_line
+        || location.end_line > lines.len()
+        || (location.start_line == location.end_line && location.start_column > location.end_column)
+    {
+        return None;
+    }
+    let first = location.start_line - 1;
+    let last = location.end_line - 1;
+
+    let first_line = lines[first].chars().collect::<Vec<_>>();
+    let last_line = lines[last].chars().collect::<Vec<_>>();
+    if location.start_column > first_line.len() || location.end_column > last_line.len() {
+        return None;
+    }
+    let prefix = first_line[..location.start_column]
+        .iter()
+        .collect::<String>();
+    let suffix = last_line[location.end_column..].iter().collect::<String>();
+
+    // the original code is printed from tokens, so the whitespace may differ from the source
+    let original = lines[first..=last].join("\n");
+    let original = &original[prefix.len()..original.len() - suffix.len()];
+    let without_whitespace = |s: &str| s.split_whitespace().collect::<String>();
+    if original.trim().is_empty()
+        || !without_whitespace(m.original_code()).starts_with(&without_whitespace(original))
+    {
+        return None;
+    }
+    let mutated = format!("{}{}{}", prefix, m.mutated_code(), suffix);
+    let mutated_lines = mutated.split('\n').collect::<Vec<_>>();
+
+    let context_start = first.saturating_sub(CONTEXT_LINES);
+    let context_end = (last + CONTEXT_LINES).min(lines.len() - 1);
+    let num_old = context_end - context_start + 1;
+    let num_new = num_old - (last - first + 1) + mutated_lines.len();
+
+    let mut diff = format!(
+        "--- a/{}\n+++ b/{}\n@@ -{},{} +{},{} @@\n",
+        m.source_file().display(),
+        m.source_file().display(),
+        context_start + 1,
+        num_old,
+        context_start + 1,
+        num_new,
+    );
+    let mut push_lines = |marker: char, lines: &[&str]| {
+        for line in lines {
+            diff.push(marker);
+            diff.push_str(line);
+            diff.push('\n');
+        }
+    };
+    push_lines(' ', &lines[context_start..first]);
+    push_lines('-', &lines[first..=last]);
+    push_lines('+', &mutated_lines);
+    push_lines(' ', &lines[last + 1..=context_end]);
+    Some(diff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::mutation_with;
+
+    const SOURCE: &str = "\
+fn foo(x: u8) -> u8 {
+    let y = 1;
+    let z = 2;
+    x + y * z
+}
+";
+
+    #[test]
+    fn diff_with_context() {
+        let diff = unified_diff(
+            SOURCE,
+            &mutation_with(1, |m| {
+                m.stub_mutator("binop_num")
+                    .stub_code("+", "-")
+                    .stub_location("4:6-4:7")
+            }),
+        )
+        .unwrap();
+
+        assert_eq!(
+            diff,
+            "\
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,5 +1,5 @@
+ fn foo(x: u8) -> u8 {
+     let y = 1;
+     let z = 2;
+-    x + y * z
++    x - y * z
+ }
+"
+        );
+    }
+
+    #[test]
+    fn diff_of_removed_statement() {
+        let diff = unified_diff(
+            SOURCE,
+            &mutation_with(1, |m| {
+                m.stub_mutator("binop_num")
+                    .stub_code("let y = 1;", "")
+                    .stub_location("2:4-2:14")
+            }),
+        )
+        .unwrap();
+
+        assert!(diff.starts_with("--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1,5 +1,5 @@\n"));
+        assert!(diff.contains("\n-    let y = 1;\n+    \n"));
+    }
+
+    #[test]
+    fn diff_of_changed_source() {
+        assert!(unified_diff(
+            SOURCE,
+            &mutation_with(1, |m| m
+                .stub_mutator("binop_num")
+                .stub_code("*", "/")
+                .stub_location("4:6-4:7"))
+        )
+        .is_none());
+        assert!(unified_diff(
+            SOURCE,
+            &mutation_with(1, |m| m
+                .stub_mutator("binop_num")
+                .stub_code("+", "-")
+                .stub_location("9:6-9:7"))
+        )
+        .is_none());
+        assert!(unified_diff(
+            SOURCE,
+            &mutation_with(1, |m| m
+                .stub_mutator("binop_num")
+                .stub_code("+", "-")
+                .stub_location("4:6-4:70"))
+        )
+        .is_none());
+        assert!(unified_diff(
+            SOURCE,
+            &mutation_with(1, |m| m
+                .stub_mutator("binop_num")
+                .stub_code("+", "-")
+                .stub_location("4:7-4:6"))
+        )
+        .is_none());
+    }
+}
@@ -0,0 +1,199 @@
+//! Selection of the mutations to test via command line options and exclusion of mutations via the configuration file.
+//!
+//! Mutations that are not selected or excluded are reported as skipped.
+
+use anyhow::{bail, Context, Result};
+use glob::{MatchOptions, Pattern};
+use std::path::Path;
+use std::str::FromStr;
+use structopt::StructOpt;
+
+use mutagen_core::comm::BakedMutation;
+
+use super::config::ExcludeConfig;
+
+/// Filters for the mutations to test.
+///
+/// Each filter can be given multiple times, in which case a mutation has to match at least one of the values.
+/// A mutation has to match all given filters to be selected.
+#[derive(StructOpt, Debug)]
+pub struct MutationFilter {
+    /// Only test mutations in source files matching the glob pattern
+    #[structopt(long = "file", name = "GLOB", number_of_values = 1)]
+    files: Vec<Pattern>,
+
+    /// Only test mutations in functions whose name matches the glob pattern
+    #[structopt(long = "fn", name = "FN_PATTERN", number_of_values = 1)]
+    fns: Vec<Pattern>,
+
+    /// Only test mutations in `impl`-blocks whose type matches the glob pattern
+    #[structopt(long = "impl", name = "IMPL_PATTERN", number_of_values = 1)]
+    impls: Vec<Pattern>,
+
+    /// Only test mutations of the given mutator, e.g. `binop_num`
+    #[structopt(long = "mutator", name = "MUTATOR", number_of_values = 1)]
+    mutators: Vec<String>,
+
+    /// Only test mutations with ids in the given range, e.g. `5` or `10-20`
+    #[structopt(long = "id", name = "RANGE", number_of_values = 1)]
+    ids: Vec<IdRange>,
+}
+
+impl MutationFilter {
+    /// Checks if the given mutation is selected by the filter.
+    ///
+    /// The path of the source file is matched relative to the workspace root and relative to the current directory.
+    pub fn matches(&self, m: &BakedMutation, workspace_root: &Path, current_dir: &Path) -> bool {
+        let match_options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        let source_file = m.source_file();
+        let source_file_in_current_dir = workspace_root.join(source_file);
+        let source_file_in_current_dir = source_file_in_current_dir
+            .strip_prefix(current_dir)
+            .unwrap_or(source_file);
+        let matches_file = |p: &Pattern| {
+            p.matches_path_with(source_file, match_options)
+                || p.matches_path_with(source_file_in_current_dir, match_options)
+        };
+        let matches_name =
+            |p: &Pattern, name: Option<&str>| name.map(|name| p.matches(name)).unwrap_or(false);
+
+        (self.files.is_empty() || self.files.iter().any(matches_file))
+            && (self.fns.is_empty() || self.fns.iter().any(|p| matches_name(p, m.fn_name())))
+            && (self.impls.is_empty() || self.impls.iter().any(|p| matches_name(p, m.impl_name())))
+            && (self.mutators.is_empty() || self.mutators.iter().any(|n| n == m.mutator_name()))
+            && (self.ids.is_empty() || self.ids.iter().any(|r| r.contains(m.id())))
+    }
+}
+
+/// Mutations excluded by the configuration file.
+///
+/// A mutation is excluded if it matches any of the exclusions.
+#[derive(Debug, Default)]
+pub struct MutationExclusions {
+    files: Vec<Pattern>,
+    fns: Vec<Pattern>,
+    mutators: Vec<String>,
+}
+
+impl MutationExclusions {
+    pub fn new(config: &ExcludeConfig) -> Result<Self> {
+        let parse_patterns = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|p| Pattern::new(p).with_context(|| format!("invalid glob pattern `{}`", p)))
+                .collect::<Result<Vec<_>>>()
+        };
+        Ok(Self {
+            files: parse_patterns(&config.files)?,
+            fns: parse_patterns(&config.fns)?,
+            mutators: config.mutators.clone(),
+        })
+    }
+
+    /// Checks if the given mutation is excluded.
+    ///
+    /// The path of the source file is matched relative to the workspace root.
+    pub fn excludes(&self, m: &BakedMutation) -> bool {
+        let match_options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        self.files
+            .iter()
+            .any(|p| p.matches_path_with(m.source_file(), match_options))
+            || m.fn_name()
+                .map(|name| self.fns.iter().any(|p| p.matches(name)))
+                .unwrap_or(false)
+            || self.mutators.iter().any(|n| n == m.mutator_name())
+    }
+}
+
+/// Inclusive range of mutation ids
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct IdRange {
+    start: usize,
+    end: usize,
+}
+
+impl IdRange {
+    fn contains(self, id: usize) -> bool {
+        self.start <= id && id <= self.end
+    }
+}
+
+impl FromStr for IdRange {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let parse_id = |id: &str| {
+            id.trim()
+                .parse::<usize>()
+                .with_context(|| format!("invalid mutation id `{}`", id))
+        };
+        let (start, end) = match s.split_once('-') {
+            Some((start, end)) => (parse_id(start)?, parse_id(end)?),
+            None => (parse_id(s)?, parse_id(s)?),
+        };
+        if start > end {
+            bail!("empty range of mutation ids `{}`", s);
+        }
+        Ok(Self { start, end })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_util::mutation_with;
+
+    #[test]
+    fn exclusions_from_config() {
+        let exclusions = MutationExclusions::new(&ExcludeConfig {
+            files: vec!["src/generated/*.rs".to_owned()],
+            fns: vec!["fmt_*".to_owned()],
+            mutators: vec!["lit_str".to_owned()],
+        })
+        .unwrap();
+
+        assert!(exclusions.excludes(&mutation_with(1, |m| m
+            .stub_source_file("src/generated/a.rs")
+            .stub_mutator("lit_int"))));
+        assert!(!exclusions.excludes(&mutation_with(1, |m| m
+            .stub_source_file("src/generated/a/b.rs")
+            .stub_mutator("lit_int"))));
+        assert!(exclusions.excludes(&mutation_with(1, |m| m
+            .stub_fn_name("fmt_debug")
+            .stub_mutator("lit_int"))));
+        assert!(exclusions.excludes(&mutation_with(1, |m| m.stub_mutator("lit_str"))));
+        assert!(!exclusions.excludes(&mutation_with(1, |m| m.stub_mutator("lit_int"))));
+    }
+
+    #[test]
+    fn id_range_single() {
+        let range = "5".parse::<IdRange>().unwrap();
+
+        assert!(range.contains(5));
+        assert!(!range.contains(4));
+        assert!(!range.contains(6));
+    }
+
+    #[test]
+    fn id_range_inclusive() {
+        let range = "10-20".parse::<IdRange>().unwrap();
+
+        assert!(range.contains(10));
+        assert!(range.contains(20));
+        assert!(!range.contains(21));
+    }
+
+    #[test]
+    fn id_range_invalid() {
+        assert!("20-10".parse::<IdRange>().is_err());
+        assert!("a-10".parse::<IdRange>().is_err());
+        assert!("".parse::<IdRange>().is_err());
+    }
+}
@@ -0,0 +1,218 @@
+//! Selection of mutations on lines that have been changed according to `git diff`.
+//!
+//! Files that are not tracked by git, but not ignored either, are considered changed entirely.
+
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str;
+
+use mutagen_core::comm::BakedMutation;
+
+/// Lines that have been changed in each file.
+#[derive(Debug)]
+pub struct ChangedLines {
+    files: HashMap<PathBuf, Vec<RangeInclusive<usize>>>,
+}
+
+impl ChangedLines {
+    /// Collects the lines changed in the working tree since the given revision, including all lines of untracked files.
+    pub fn since(rev: &str) -> Result<Self> {
+        let git_root = git(&["rev-parse", "--show-toplevel"])?;
+        let git_root = Path::new(git_root.trim());
+
+        let diff = git(&[
+            "diff",
+            "--unified=0",
+            "--no-color",
+            "--no-ext-diff",
+            "--src-prefix=a/",
+            "--dst-prefix=b/",
+            rev,
+        ])
+        .with_context(|| format!("unable to compute changes since `{}`", rev))?;
+
+        let untracked = git(&["ls-files", "--others", "--exclude-standard", "--full-name"])
+            .context("unable to list the untracked files")?;
+
+        let files = parse_diff(&diff)
+            .into_iter()
+            .chain(parse_untracked(&untracked))
+            .map(|(file, lines)| (canonicalize(&git_root.join(file)), lines))
+            .collect();
+        Ok(Self { files })
+    }
+
+    /// Checks if the given mutation is on a changed line.
+    ///
+    /// The path of the source file of the mutation is relative to the workspace root.
+    /// Mutations with unknown location are considered changed.
+    pub fn contains_mutation(&self, m: &BakedMutation, workspace_root: &Path) -> bool {
+        let source_file = canonicalize(&workspace_root.join(m.source_file()));
+        let changed_lines = match self.files.get(&source_file) {
+            Some(changed_lines) => changed_lines,
+            None => return false,
+        };
+        match m.location() {
+            Ok(location) => changed_lines.iter().any(|lines| {
+                location.start_line <= *lines.end() && *lines.start() <= location.end_line
+            }),
+            Err(_) => true,
+        }
+    }
+}
+
+/// parses the output of `git diff --unified=0` into the ranges of added or modified lines per file.
+///
+/// The paths are relative to the root of the git repository.
+fn parse_diff(diff: &str) -> HashMap<PathBuf, Vec<RangeInclusive<usize>>> {
+    let mut files = HashMap::new();
+    let mut current_file: Option<PathBuf> = None;
+
+    for line in diff.lines() {
+        if let Some(path) = line.strip_prefix("+++ ") {
+            // deleted files are written as `/dev/null`
+            current_file = path.strip_prefix("b/").map(PathBuf::from);
+        } else if let Some(hunk) = line.strip_prefix("@@ ") {
+            let file = match &current_file {
+                Some(file) => file,
+                None => continue,
+            };
+            if let Some(lines) = parse_hunk_header(hunk) {
+                files
+                    .entry(file.clone())
+                    .or_insert_with(Vec::new)
+                    .push(lines);
+            }
+        }
+    }
+    files
+}
+
+/// parses the output of `git ls-files --others`, where all lines of the untracked files are new.
+///
+/// The paths are relative to the root of the git repository.
+fn parse_untracked(untracked: &str) -> Vec<(PathBuf, Vec<RangeInclusive<usize>>)> {
+    untracked
+        .lines()
+        .map(|file| (PathBuf::from(file), vec![1..=usize::MAX]))
+        .collect()
+}
+
+/// parses the lines of the new file from a hunk header of the form `-a,b +c,d @@`.
+///
+/// Returns `None` if no lines have been added in this hunk.
+fn parse_hunk_header(hunk: &str) -> Option<RangeInclusive<usize>> {
+    let new_lines = hunk.split_whitespace().find(|s| s.starts_with('+'))?;
+    let mut parts = new_lines[1..].splitn(2, ',');
+    let start = parts.next()?.parse::<usize>().ok()?;
+    let count = match parts.next() {
+        Some(count) => count.parse::<usize>().ok()?,
+        None => 1,
+    };
+    if count == 0 {
+        None
+    } else {
+        Some(start..=start + count - 1)
+    }
+}
+
+fn git(args: &[&str]) -> Result<String> {
+    let output = Command::new("git").args(args).output()?;
+    if !output.status.success() {
+        bail!("{}", str::from_utf8(&output.stderr)?.trim());
+    }
+    Ok(str::from_utf8(&output.stdout)?.to_owned())
+}
+
+fn canonicalize(path: &Path) -> PathBuf {
+    path.canonicalize().unwrap_or_else(|_| path.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::mutation_with;
+
+    #[test]
+    fn hunk_header_with_count() {
+        assert_eq!(parse_hunk_header("-10,2 +12,3 @@ fn foo()"), Some(12..=14));
+    }
+
+    #[test]
+    fn hunk_header_single_line() {
+        assert_eq!(parse_hunk_header("-10 +12 @@"), Some(12..=12));
+    }
+
+    #[test]
+    fn hunk_header_only_removed_lines() {
+        assert_eq!(parse_hunk_header("-10,2 +9,0 @@"), None);
+    }
+
+    #[test]
+    fn parse_diff_multiple_files() {
+        let diff = "\
+diff --git a/src/lib.rs b/src/lib.rs
+index 1111111..2222222 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -3 +3 @@ fn foo() {
+-    1 + 2
++    1 - 2
+@@ -10,0 +11,2 @@ fn bar() {
++    let x = 1;
++    let y = 2;
+diff --git a/src/old.rs b/src/old.rs
+deleted file mode 100644
+--- a/src/old.rs
++++ /dev/null
+@@ -1,2 +0,0 @@
+-fn old() {}
+-
+";
+        let files = parse_diff(diff);
+
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[Path::new("src/lib.rs")], vec![3..=3, 11..=12]);
+    }
+
+    #[test]
+    fn untracked_files_are_changed_entirely() {
+        let files = parse_untracked("src/new.rs\nsrc/other.rs\n");
+
+        assert_eq!(
+            files,
+            vec![
+                (PathBuf::from("src/new.rs"), vec![1..=usize::MAX]),
+                (PathBuf::from("src/other.rs"), vec![1..=usize::MAX]),
+            ]
+        );
+    }
+
+    #[test]
+    fn contains_mutations_on_changed_lines() {
+        let workspace_root = Path::new("/workspace");
+        let changed_lines = ChangedLines {
+            files: vec![(workspace_root.join("src/lib.rs"), vec![3..=3])]
+                .into_iter()
+                .collect(),
+        };
+        let mutation = |source_file, location| {
+            mutation_with(1, |m| {
+                m.stub_source_file(source_file).stub_location(location)
+            })
+        };
+
+        assert!(changed_lines.contains_mutation(&mutation("src/lib.rs", "3:4-3:5"), workspace_root));
+        assert!(
+            !changed_lines.contains_mutation(&mutation("src/lib.rs", "5:4-5:5"), workspace_root)
+        );
+        assert!(
+            !changed_lines.contains_mutation(&mutation("src/main.rs", "3:4-3:5"), workspace_root)
+        );
+        // mutations with an unknown location in a changed file are tested
+        assert!(changed_lines.contains_mutation(&mutation("src/lib.rs", "unknown"), workspace_root));
+    }
+}
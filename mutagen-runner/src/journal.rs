@@ -0,0 +1,171 @@
+//! Journal of the results of the current run, used to resume an interrupted run.
+//!
+//! Each result is appended to `target/mutagen/journal` as soon as the mutant has been tested.
+//! The journal starts with a fingerprint of the mutations and test binaries.
+//! A run can only be resumed if the fingerprint is unchanged, since the mutation ids would be meaningless otherwise.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use mutagen_core::comm::{self, KillDetails, MutantStatus};
+
+use super::test_bin::hash_test_bins;
+use super::TestBinTested;
+
+/// A single entry in the journal file
+#[derive(Serialize, Deserialize)]
+enum JournalEntry {
+    /// first entry of the journal, identifying the mutations and test binaries of the run
+    Start { fingerprint: String },
+    Result {
+        mutation_id: usize,
+        status: MutantStatus,
+        kill_details: Option<KillDetails>,
+    },
+}
+
+pub struct Journal {
+    journal_file: File,
+    resumed: bool,
+    /// results of an interrupted run
+    decided: HashMap<usize, (MutantStatus, Option<KillDetails>)>,
+}
+
+impl Journal {
+    /// Starts a new journal for the given test binaries.
+    ///
+    /// If `resume` is set and the journal in `target/mutagen` has been written for the same mutations and test binaries,
+    /// the journal is continued and its results are reused.
+    pub fn open(test_bins: &[TestBinTested], resume: bool) -> Result<Self> {
+        let mut fingerprint = Sha256::new();
+        fingerprint.update(fs::read(comm::get_mutations_file()?)?);
+        fingerprint.update(hash_test_bins(test_bins)?);
+        let fingerprint = format!("{:x}", fingerprint.finalize());
+
+        Self::open_at(&comm::get_journal_file()?, fingerprint, resume)
+    }
+
+    /// Starts a new journal at the given path, which is continued if it has been written with the same fingerprint.
+    pub fn open_at(journal_path: &Path, fingerprint: String, resume: bool) -> Result<Self> {
+        let journal = if resume && journal_path.exists() {
+            fs::read_to_string(journal_path)?
+        } else {
+            String::new()
+        };
+        let decided = read_journal(&journal, &fingerprint);
+
+        let journal_file = match decided {
+            Some(_) => {
+                let mut journal_file = OpenOptions::new().append(true).open(journal_path)?;
+                // terminate an incomplete entry of the interrupted run
+                if !journal.ends_with('\n') {
+                    writeln!(journal_file)?;
+                }
+                journal_file
+            }
+            None => {
+                let mut journal_file = File::create(journal_path)?;
+                comm::append_item(&mut journal_file, &JournalEntry::Start { fingerprint })?;
+                journal_file
+            }
+        };
+
+        Ok(Self {
+            journal_file,
+            resumed: decided.is_some(),
+            decided: decided.unwrap_or_default(),
+        })
+    }
+
+    /// Checks if the journal of an earlier run is continued.
+    pub fn is_resumed(&self) -> bool {
+        self.resumed
+    }
+
+    /// number of mutants that have been decided in the resumed run
+    pub fn num_decided(&self) -> usize {
+        self.decided.len()
+    }
+
+    /// Returns the result of a mutant from the resumed run.
+    pub fn get(&self, mutation_id: usize) -> Option<(MutantStatus, Option<KillDetails>)> {
+        self.decided.get(&mutation_id).cloned()
+    }
+
+    /// Appends the result of a mutant to the journal.
+    pub fn insert(
+        &mut self,
+        mutation_id: usize,
+        status: MutantStatus,
+        kill_details: Option<&KillDetails>,
+    ) -> Result<()> {
+        comm::append_item(
+            &mut self.journal_file,
+            &JournalEntry::Result {
+                mutation_id,
+                status,
+                kill_details: kill_details.cloned(),
+            },
+        )
+    }
+}
+
+/// reads the results from a journal with the given fingerprint.
+///
+/// Returns `None` if the journal has been written for a different fingerprint.
+/// Entries that cannot be read, like an incomplete last line of an interrupted run, are ignored.
+fn read_journal(
+    journal: &str,
+    fingerprint: &str,
+) -> Option<HashMap<usize, (MutantStatus, Option<KillDetails>)>> {
+    let mut entries = journal
+        .lines()
+        .filter_map(|line| serde_json::from_str::<JournalEntry>(line).ok());
+    match entries.next() {
+        Some(JournalEntry::Start { fingerprint: f }) if f == fingerprint => {}
+        _ => return None,
+    }
+    let decided = entries
+        .filter_map(|entry| match entry {
+            JournalEntry::Result {
+                mutation_id,
+                status,
+                kill_details,
+            } => Some((mutation_id, (status, kill_details))),
+            JournalEntry::Start { .. } => None,
+        })
+        .collect();
+    Some(decided)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_journal_with_incomplete_entry() {
+        let journal = r#"{"Start":{"fingerprint":"abc"}}
+{"Result":{"mutation_id":1,"status":"Survived","kill_details":null}}
+{"Result":{"mutation_id":2,"status":{"Killed":101},"kill_details":{"failed_tests":["a"],"panic_message":null}}}
+{"Result":{"mutation_id":3,"sta"#;
+
+        let decided = read_journal(journal, "abc").unwrap();
+
+        assert_eq!(decided.len(), 2);
+        assert_eq!(decided[&1], (MutantStatus::Survived, None));
+        assert_eq!(decided[&2].0, MutantStatus::Killed(Some(101)));
+    }
+
+    #[test]
+    fn read_journal_with_other_fingerprint() {
+        let journal = r#"{"Start":{"fingerprint":"abc"}}
+{"Result":{"mutation_id":1,"status":"Survived","kill_details":null}}"#;
+
+        assert!(read_journal(journal, "def").is_none());
+    }
+}
@@ -0,0 +1,237 @@
+//! History of the tests that killed mutants, used to test each mutant first with the tests most likely to kill it.
+//!
+//! Mutants in the same function or file tend to be killed by the same tests.
+//! Each kill is recorded with its test suite and failed tests, in memory for the current run and in `target/mutagen/kill-history` for later runs.
+//! The test suites are tried in the order of their kills of mutants in the same function, then in the same file, and then by the runtime of their unmutated run.
+//! Within a test suite, the tests that killed mutants in the same function or file are run before the remaining tests.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use mutagen_core::comm::{self, BakedMutation, KillDetails};
+
+use super::TestBinTested;
+
+/// number of kills kept in the history file, the oldest kills are dropped first
+const MAX_KILLS: usize = 10_000;
+
+/// maximal number of tests that are run before the remaining tests of a test suite
+const MAX_FIRST_TESTS: usize = 10;
+
+/// A mutant that has been killed by a test suite
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Kill {
+    source_file: PathBuf,
+    impl_name: Option<String>,
+    fn_name: Option<String>,
+    /// name of the test suite that killed the mutant
+    testsuite: String,
+    failed_tests: Vec<String>,
+}
+
+/// how close a recorded kill is to a mutant
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Proximity {
+    SameFn,
+    SameFile,
+}
+
+impl Kill {
+    fn proximity(&self, m: &BakedMutation) -> Option<Proximity> {
+        if self.source_file != m.source_file() {
+            None
+        } else if self.fn_name.is_some()
+            && self.fn_name.as_deref() == m.fn_name()
+            && self.impl_name.as_deref() == m.impl_name()
+        {
+            Some(Proximity::SameFn)
+        } else {
+            Some(Proximity::SameFile)
+        }
+    }
+}
+
+/// number of kills in the same function and in the same file as a mutant
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct KillCount {
+    same_fn: usize,
+    same_file: usize,
+}
+
+impl KillCount {
+    fn add(&mut self, proximity: Proximity) {
+        match proximity {
+            Proximity::SameFn => self.same_fn += 1,
+            Proximity::SameFile => self.same_file += 1,
+        }
+    }
+}
+
+/// The kills of earlier mutants, shared by all jobs testing mutants
+pub struct KillHistory {
+    kills: Mutex<Vec<Kill>>,
+    history_file: Mutex<File>,
+}
+
+impl KillHistory {
+    /// Reads the kills of earlier runs from `target/mutagen/kill-history`.
+    ///
+    /// Kills that cannot be read are ignored, since the history only affects the order of the tests.
+    pub fn open() -> Result<Self> {
+        let history_path = comm::get_kill_history_file()?;
+        let history = fs::read_to_string(&history_path).unwrap_or_default();
+        let mut kills = read_kills(&history);
+        kills.drain(..kills.len().saturating_sub(MAX_KILLS));
+
+        // rewrite the history to drop unreadable and old kills
+        let mut history_file = File::create(&history_path)?;
+        for kill in &kills {
+            comm::append_item(&mut history_file, kill)?;
+        }
+
+        Ok(Self {
+            kills: Mutex::new(kills),
+            history_file: Mutex::new(history_file),
+        })
+    }
+
+    /// Orders the test suites by how likely they kill the given mutant.
+    ///
+    /// Each test suite is returned with the tests that should be run before its remaining tests.
+    pub fn order<'b, 'a>(
+        &self,
+        m: &BakedMutation,
+        test_bins: &'b [TestBinTested<'a>],
+    ) -> Vec<(&'b TestBinTested<'a>, Vec<String>)> {
+        let kills = self.kills.lock().unwrap();
+        let mut ordered = test_bins
+            .iter()
+            .map(|bin| {
+                let (count, first_tests) = nearby_kills(&kills, m, &bin.name());
+                (bin, count, first_tests)
+            })
+            .collect::<Vec<_>>();
+        ordered.sort_by_key(|(bin, count, _)| (Reverse(*count), bin.exe_time));
+        ordered
+            .into_iter()
+            .map(|(bin, _, first_tests)| (bin, first_tests))
+            .collect()
+    }
+
+    /// Records that the given test suite killed the mutant.
+    pub fn insert(
+        &self,
+        m: &BakedMutation,
+        testsuite: &str,
+        kill_details: Option<&KillDetails>,
+    ) -> Result<()> {
+        let kill = Kill {
+            source_file: m.source_file().to_owned(),
+            impl_name: m.impl_name().map(|s| s.to_owned()),
+            fn_name: m.fn_name().map(|s| s.to_owned()),
+            testsuite: testsuite.to_owned(),
+            failed_tests: kill_details
+                .map(|d| d.failed_tests.clone())
+                .unwrap_or_default(),
+        };
+        comm::append_item(&mut self.history_file.lock().unwrap(), &kill)?;
+        self.kills.lock().unwrap().push(kill);
+        Ok(())
+    }
+}
+
+/// reads the kills from the history, skipping lines that cannot be read
+fn read_kills(history: &str) -> Vec<Kill> {
+    history
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Counts the kills of the test suite near the mutant and collects the tests to run first.
+///
+/// The tests are ordered by their number of kills in the same function and then in the same file.
+fn nearby_kills(kills: &[Kill], m: &BakedMutation, testsuite: &str) -> (KillCount, Vec<String>) {
+    let mut count = KillCount::default();
+    let mut test_counts = HashMap::<&str, KillCount>::new();
+    for kill in kills.iter().filter(|k| k.testsuite == testsuite) {
+        if let Some(proximity) = kill.proximity(m) {
+            count.add(proximity);
+            for test in &kill.failed_tests {
+                test_counts.entry(test).or_default().add(proximity);
+            }
+        }
+    }
+    let mut tests = test_counts.into_iter().collect::<Vec<_>>();
+    tests.sort_by_key(|(test, count)| (Reverse(*count), *test));
+    let first_tests = tests
+        .into_iter()
+        .take(MAX_FIRST_TESTS)
+        .map(|(test, _)| test.to_owned())
+        .collect();
+    (count, first_tests)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::mutation_with;
+
+    fn kill(source_file: &str, fn_name: &str, testsuite: &str, failed_tests: &[&str]) -> Kill {
+        Kill {
+            source_file: source_file.into(),
+            impl_name: None,
+            fn_name: Some(fn_name.to_owned()),
+            testsuite: testsuite.to_owned(),
+            failed_tests: failed_tests.iter().map(|t| t.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn kills_in_same_fn_before_same_file() {
+        let kills = vec![
+            kill("src/a.rs", "foo", "unit", &["tests::foo"]),
+            kill("src/a.rs", "bar", "unit", &["tests::bar"]),
+            kill("src/a.rs", "bar", "unit", &["tests::bar"]),
+            kill("src/a.rs", "foo", "integration", &["foo"]),
+            kill("src/b.rs", "foo", "unit", &["tests::b"]),
+        ];
+        let m = mutation_with(1, |m| m.stub_source_file("src/a.rs"));
+
+        let (count, first_tests) = nearby_kills(&kills, &m, "unit");
+        assert_eq!(
+            count,
+            KillCount {
+                same_fn: 1,
+                same_file: 2
+            }
+        );
+        assert_eq!(first_tests, vec!["tests::foo", "tests::bar"]);
+
+        let (count, first_tests) = nearby_kills(&kills, &m, "integration");
+        assert_eq!(
+            count,
+            KillCount {
+                same_fn: 1,
+                same_file: 0
+            }
+        );
+        assert_eq!(first_tests, vec!["foo"]);
+
+        assert!(count < nearby_kills(&kills, &m, "unit").0);
+        assert_eq!(nearby_kills(&kills, &m, "other").0, KillCount::default());
+    }
+
+    #[test]
+    fn read_kills_skips_invalid_lines() {
+        let kill = serde_json::to_string(&kill("src/a.rs", "foo", "unit", &[])).unwrap();
+        let history = format!("{}\n{{\"source_file\n{}\n", kill, kill);
+
+        assert_eq!(read_kills(&history).len(), 2);
+    }
+}
@@ -1,6 +1,39 @@
+mod baseline;
+mod cache;
+mod config;
+mod diff;
+mod filter;
+mod git_diff;
+mod journal;
+mod kill_history;
+mod limits;
+mod nextest;
 mod progress;
 mod progress_bar;
+mod report;
+mod score;
+mod shard;
+mod suppression;
 mod test_bin;
+mod test_output;
+#[cfg(test)]
+mod test_util;
+mod timeout;
 
+pub use baseline::BaselineComparison;
+pub use cache::MutationCache;
+pub use config::{cli_flag, BuildConfig, Config, ExcludeConfig, ScoreConfig};
+pub use diff::print_survived_diffs;
+pub use filter::{MutationExclusions, MutationFilter};
+pub use git_diff::ChangedLines;
+pub use journal::Journal;
+pub use kill_history::KillHistory;
+pub use limits::{LimitConfig, LimitOptions, ResourceLimits};
+pub use nextest::{NextestBuild, TestRunner};
 pub use progress::Progress;
-pub use test_bin::{TestBin, TestBinTested};
+pub use report::ReportFormat;
+pub use score::{parse_min_score, ScoreThresholds};
+pub use shard::{merge_reports, read_json_report, Shard};
+pub use suppression::Suppressions;
+pub use test_bin::{TestBin, TestBinTested, TestSuite};
+pub use timeout::{TimeoutConfig, TimeoutOptions, TimeoutPolicy};
@@ -0,0 +1,292 @@
+//! Limits of the resources of the test suites while testing mutants.
+//!
+//! Mutations like flipping a loop bound can make tests allocate memory, spawn processes or write files without bound.
+//! The limits are applied as rlimits to each run of a test suite against a mutant, but not to the unmutated runs.
+//! They are inherited by all processes started by the test suite, including cargo for nextest.
+//! Limits cannot be combined with doctests, since `cargo test --doc` compiles the doctests on each run and the limits would apply to the compiler.
+//! A mutant that exceeds one of the limits is reported as killed by exceeding this limit.
+//! Resource limits are only supported on unix.
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::process::{Command, ExitStatus};
+use std::time::Duration;
+use structopt::StructOpt;
+
+use mutagen_core::comm::ResourceLimit;
+
+/// Command line options for the resource limits
+#[derive(StructOpt, Debug)]
+pub struct LimitOptions {
+    /// Maximal address space of each test process when testing mutants, e.g. `4GiB`
+    #[structopt(long, name = "MEMORY", parse(try_from_str = parse_size))]
+    limit_memory: Option<u64>,
+
+    /// Maximal CPU time of each test process when testing mutants, e.g. `1m`
+    #[structopt(long, name = "CPU_TIME")]
+    limit_cpu: Option<humantime::Duration>,
+
+    /// Maximal number of processes and threads of the user when testing mutants
+    #[structopt(long, name = "PROCESSES")]
+    limit_processes: Option<u64>,
+
+    /// Maximal size of each file written when testing mutants, e.g. `1GiB`
+    #[structopt(long, name = "FILE_SIZE", parse(try_from_str = parse_size))]
+    limit_file_size: Option<u64>,
+}
+
+/// Resource limits in the configuration file
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitConfig {
+    /// maximal address space of each process, e.g. `"4GiB"`
+    pub memory: Option<String>,
+    /// maximal CPU time of each process, e.g. `"1m"`
+    pub cpu: Option<String>,
+    /// maximal number of processes and threads of the user
+    pub processes: Option<u64>,
+    /// maximal size of each written file, e.g. `"1GiB"`
+    pub file_size: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ResourceLimits {
+    memory: Option<u64>,
+    cpu: Option<Duration>,
+    processes: Option<u64>,
+    file_size: Option<u64>,
+}
+
+impl ResourceLimits {
+    /// Creates the limits from the configuration file.
+    ///
+    /// The options from the command line take precedence over the configuration.
+    pub fn new(opt: &LimitOptions, config: &LimitConfig) -> Result<Self> {
+        let memory = match (opt.limit_memory, &config.memory) {
+            (Some(memory), _) => Some(memory),
+            (None, Some(memory)) => Some(parse_size(memory)?),
+            (None, None) => None,
+        };
+        let cpu = match (opt.limit_cpu, &config.cpu) {
+            (Some(cpu), _) => Some(cpu.into()),
+            (None, Some(cpu)) => Some(
+                humantime::parse_duration(cpu)
+                    .with_context(|| format!("invalid CPU time `{}`", cpu))?,
+            ),
+            (None, None) => None,
+        };
+        let file_size = match (opt.limit_file_size, &config.file_size) {
+            (Some(file_size), _) => Some(file_size),
+            (None, Some(file_size)) => Some(parse_size(file_size)?),
+            (None, None) => None,
+        };
+        let limits = Self {
+            memory,
+            cpu,
+            processes: opt.limit_processes.or(config.processes),
+            file_size,
+        };
+        if cfg!(not(unix)) && limits.is_limited() {
+            bail!("resource limits are only supported on unix");
+        }
+        Ok(limits)
+    }
+
+    /// Checks if any limit is set.
+    pub fn is_limited(&self) -> bool {
+        self.memory.is_some()
+            || self.cpu.is_some()
+            || self.processes.is_some()
+            || self.file_size.is_some()
+    }
+
+    /// Applies the limits to the process started by the command.
+    ///
+    /// Limits above the current hard limits of the runner are lowered to the hard limits.
+    pub fn apply(&self, command: &mut Command) {
+        #[cfg(unix)]
+        if self.is_limited() {
+            use std::os::unix::process::CommandExt;
+
+            // the CPU time is rounded up to full seconds.
+            // The process gets `SIGXCPU` at the soft limit and is killed one second later.
+            let cpu = self.cpu.map(|cpu| {
+                let secs = cpu.as_secs() + u64::from(cpu.subsec_nanos() > 0);
+                (secs, secs + 1)
+            });
+            let limits = [
+                (libc::RLIMIT_AS, self.memory.map(|m| (m, m))),
+                (libc::RLIMIT_CPU, cpu),
+                (libc::RLIMIT_NPROC, self.processes.map(|p| (p, p))),
+                (libc::RLIMIT_FSIZE, self.file_size.map(|f| (f, f))),
+            ];
+            // only functions that are safe to call after `fork` are used in the child
+            unsafe {
+                command.pre_exec(move || {
+                    for (resource, limit) in limits {
+                        if let Some((soft, hard)) = limit {
+                            set_rlimit(resource, soft, hard)?;
+                        }
+                    }
+                    Ok(())
+                });
+            }
+        }
+    }
+
+    /// Checks if a run of a test suite failed because it exceeded one of the limits.
+    ///
+    /// Exceeding the CPU time or file size is signaled to the process.
+    /// Failed allocations and failures to spawn processes or threads are detected by their error messages in the output.
+    pub fn exceeded(
+        &self,
+        status: ExitStatus,
+        stdout: &str,
+        stderr: &str,
+    ) -> Option<ResourceLimit> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            match status.signal() {
+                Some(libc::SIGXCPU) if self.cpu.is_some() => return Some(ResourceLimit::Cpu),
+                Some(libc::SIGXFSZ) if self.file_size.is_some() => {
+                    return Some(ResourceLimit::FileSize)
+                }
+                _ => {}
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = status;
+
+        let output_contains = |pattern: &str| stdout.contains(pattern) || stderr.contains(pattern);
+        if self.memory.is_some()
+            && (output_contains("memory allocation of ")
+                || output_contains("Cannot allocate memory"))
+        {
+            Some(ResourceLimit::Memory)
+        } else if self.processes.is_some() && output_contains("Resource temporarily unavailable") {
+            Some(ResourceLimit::Processes)
+        } else {
+            None
+        }
+    }
+}
+
+/// type of the resources of `setrlimit`, which differs between the C libraries
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type Resource = libc::__rlimit_resource_t;
+#[cfg(all(unix, not(all(target_os = "linux", target_env = "gnu"))))]
+type Resource = libc::c_int;
+
+/// sets the soft and hard limit of a resource of the current process, bounded by its current hard limit
+#[cfg(unix)]
+fn set_rlimit(resource: Resource, soft: u64, hard: u64) -> std::io::Result<()> {
+    let mut current = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    if unsafe { libc::getrlimit(resource, &mut current) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    let hard = (hard as libc::rlim_t).min(current.rlim_max);
+    let limit = libc::rlimit {
+        rlim_cur: (soft as libc::rlim_t).min(hard),
+        rlim_max: hard,
+    };
+    if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// parses a size in bytes, e.g. `512MiB` or `4G`.
+///
+/// The suffixes `K`, `M`, `G` and `T` are binary, with or without `iB`; `KB`, `MB`, `GB` and `TB` are decimal.
+fn parse_size(s: &str) -> Result<u64> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number = number
+        .parse::<u64>()
+        .with_context(|| format!("invalid size `{}`", s))?;
+    let factor: u64 = match unit.trim() {
+        "" | "B" => 1,
+        "K" | "KiB" => 1 << 10,
+        "M" | "MiB" => 1 << 20,
+        "G" | "GiB" => 1 << 30,
+        "T" | "TiB" => 1 << 40,
+        "KB" => 1_000,
+        "MB" => 1_000_000,
+        "GB" => 1_000_000_000,
+        "TB" => 1_000_000_000_000,
+        _ => bail!("invalid unit of size `{}`", s),
+    };
+    number
+        .checked_mul(factor)
+        .with_context(|| format!("size `{}` is too large", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sizes() {
+        assert_eq!(parse_size("1024").unwrap(), 1024);
+        assert_eq!(parse_size("512MiB").unwrap(), 512 << 20);
+        assert_eq!(parse_size("4G").unwrap(), 4 << 30);
+        assert_eq!(parse_size("2 GB").unwrap(), 2_000_000_000);
+        assert!(parse_size("4 Gibibytes").is_err());
+        assert!(parse_size("GiB").is_err());
+        assert!(parse_size("99999999999T").is_err());
+    }
+
+    #[test]
+    fn limits_from_config() {
+        let opt = LimitOptions::from_iter(&["mutagen", "--limit-memory", "1GiB"]);
+        let config = LimitConfig {
+            memory: Some("4GiB".to_owned()),
+            cpu: Some("90s".to_owned()),
+            processes: Some(512),
+            file_size: None,
+        };
+        let limits = ResourceLimits::new(&opt, &config).unwrap();
+
+        assert_eq!(limits.memory, Some(1 << 30));
+        assert_eq!(limits.cpu, Some(Duration::from_secs(90)));
+        assert_eq!(limits.processes, Some(512));
+        assert_eq!(limits.file_size, None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn detect_exceeded_limits() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let limits = ResourceLimits {
+            memory: Some(1 << 30),
+            cpu: Some(Duration::from_secs(10)),
+            processes: None,
+            file_size: None,
+        };
+        let aborted = ExitStatus::from_raw(libc::SIGABRT);
+        let failed = ExitStatus::from_raw(101 << 8);
+
+        assert_eq!(
+            limits.exceeded(ExitStatus::from_raw(libc::SIGXCPU), "", ""),
+            Some(ResourceLimit::Cpu)
+        );
+        assert_eq!(
+            limits.exceeded(aborted, "", "memory allocation of 4096 bytes failed\n"),
+            Some(ResourceLimit::Memory)
+        );
+        assert_eq!(
+            limits.exceeded(ExitStatus::from_raw(libc::SIGXFSZ), "", ""),
+            None
+        );
+        assert_eq!(
+            limits.exceeded(failed, "Resource temporarily unavailable", ""),
+            None
+        );
+    }
+}
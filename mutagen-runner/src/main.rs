@@ -1,17 +1,22 @@
 use anyhow::{bail, Result};
 use std::collections::HashMap;
 use std::env;
+use std::ffi::OsStr;
 use std::fs::File;
 use std::io::BufWriter;
 use std::path::PathBuf;
 use std::process;
 use std::process::{Command, Stdio};
 use std::str;
+use std::sync::{mpsc, Mutex};
+use std::thread;
 use std::time::Instant;
 
 use cargo_mutagen::*;
 use mutagen_core::comm;
-use mutagen_core::comm::{BakedMutation, CoverageCollection, MutagenReport, MutantStatus};
+use mutagen_core::comm::{
+    BakedMutation, CoverageCollection, JsonReport, KillDetails, MutagenReport, MutantStatus,
+};
 
 fn main() {
     if let Err(err) = run() {
@@ -30,16 +35,157 @@ struct Options {
     features: Option<String>,
 
     /// Activate all available features
-    #[structopt(long)]
+    #[structopt(long, overrides_with = "no-all-features")]
     all_features: bool,
 
+    /// Do not activate all features, even if the configuration does
+    #[structopt(long, overrides_with = "all-features")]
+    no_all_features: bool,
+
+    /// Do not activate the `default` feature
+    #[structopt(long)]
+    no_default_features: bool,
+
     /// Package to run tests for
     #[structopt(long, name = "SPEC")]
     package: Option<String>,
 
     /// Test all packages in the workspace
-    #[structopt(long)]
+    #[structopt(long, overrides_with = "no-workspace")]
     workspace: bool,
+
+    /// Do not test all packages in the workspace, even if the configuration does
+    #[structopt(long, overrides_with = "workspace")]
+    no_workspace: bool,
+
+    /// Build the test suites in release mode, with optimizations
+    #[structopt(long)]
+    release: bool,
+
+    /// Build the test suites with the given profile
+    #[structopt(long, name = "PROFILE-NAME")]
+    profile: Option<String>,
+
+    /// Directory for all generated artifacts of cargo
+    #[structopt(long, name = "DIRECTORY", parse(from_os_str))]
+    target_dir: Option<PathBuf>,
+
+    /// Test only the library of the package
+    #[structopt(long)]
+    lib: bool,
+
+    /// Test only the given integration test
+    #[structopt(long = "test", name = "NAME", number_of_values = 1)]
+    tests: Vec<String>,
+
+    /// Test all binaries of the package
+    #[structopt(long)]
+    bins: bool,
+
+    /// Run only the tests that cover the mutation, instead of the whole test suite
+    #[structopt(long)]
+    coverage: bool,
+
+    /// Number of mutants to test in parallel
+    #[structopt(long, short = "j", name = "N", default_value = "1")]
+    jobs: usize,
+
+    /// Only test mutations on lines changed since the given git revision
+    #[structopt(long, name = "REV")]
+    diff: Option<String>,
+
+    #[structopt(flatten)]
+    filter: MutationFilter,
+
+    /// Only test the `k`-th of `n` disjoint subsets of the mutations, e.g. `2/5`
+    #[structopt(long, name = "k/n")]
+    shard: Option<Shard>,
+
+    /// Do not reuse results of unchanged mutants from earlier runs
+    #[structopt(long)]
+    no_cache: bool,
+
+    /// Continue an interrupted run, skipping the mutants that have already been tested
+    #[structopt(long)]
+    resume: bool,
+
+    /// Additional report formats to write to `target/mutagen`
+    #[structopt(long, name = "FORMAT", possible_values = ReportFormat::NAMES, number_of_values = 1, global = true)]
+    report: Vec<ReportFormat>,
+
+    #[structopt(flatten)]
+    timeout: TimeoutOptions,
+
+    #[structopt(flatten)]
+    limits: LimitOptions,
+
+    /// Show the survived mutants as unified diffs of their source files
+    #[structopt(long, global = true)]
+    show_diff: bool,
+
+    /// Fail if the mutation score in percent is below the given minimum
+    #[structopt(long, name = "PERCENT", global = true, parse(try_from_str = parse_min_score))]
+    min_score: Option<f64>,
+
+    /// Exclude mutants that are not covered by any test from the mutation score
+    #[structopt(long, global = true, overrides_with = "no-ignore-not-covered")]
+    ignore_not_covered: bool,
+
+    /// Include mutants that are not covered by any test in the mutation score, even if the configuration excludes them
+    #[structopt(long, global = true, overrides_with = "ignore-not-covered")]
+    no_ignore_not_covered: bool,
+
+    /// Compare the results with a report of an earlier run and fail if any mutant newly survives
+    #[structopt(long, name = "BASELINE_REPORT", parse(from_os_str), global = true)]
+    baseline: Option<PathBuf>,
+
+    /// Backend that runs the tests: the test-binaries of `cargo test` (libtest) or cargo-nextest
+    #[structopt(long, name = "RUNNER", possible_values = TestRunner::NAMES)]
+    runner: Option<TestRunner>,
+
+    /// Also run the doctests of the library against each mutant, via `cargo test --doc`
+    #[structopt(long)]
+    doctests: bool,
+
+    /// Arguments passed to the test binaries, e.g. test filters, `--test-threads N` or `--include-ignored`.
+    /// With nextest, only filters, `--exact`, `--skip`, `--ignored`, `--include-ignored`, `--test-threads` and `--nocapture` are supported
+    #[structopt(name = "TEST_ARGS", last = true)]
+    test_args: Vec<String>,
+
+    #[structopt(subcommand)]
+    command: Option<Subcommand>,
+}
+
+impl Options {
+    /// use the settings of the configuration file that are not given on the command line
+    fn apply_config(&mut self, config: &Config) {
+        let build = &config.build;
+        if self.features.is_none() && !build.features.is_empty() {
+            self.features = Some(build.features.join(" "));
+        }
+        self.all_features =
+            cli_flag(self.all_features, self.no_all_features).unwrap_or(build.all_features);
+        if self.package.is_none() {
+            self.package = build.package.clone();
+        }
+        self.workspace = cli_flag(self.workspace, self.no_workspace).unwrap_or(build.workspace);
+        if self.runner.is_none() {
+            self.runner = build.runner;
+        }
+        if self.report.is_empty() {
+            self.report = config.report.clone();
+        }
+    }
+}
+
+#[derive(StructOpt, Debug)]
+enum Subcommand {
+    /// Merge the reports written by several shards into a single report
+    Merge {
+        /// Reports of the shards, written to `target/mutagen/report.json`
+        #[structopt(name = "REPORT", required = true, parse(from_os_str))]
+        reports: Vec<PathBuf>,
+    },
 }
 
 fn run() -> Result<()> {
@@ -51,28 +197,93 @@ fn run() -> Result<()> {
         // we're invoked by cargo, drop the first arg
         args.next();
     }
-    let opt = Options::from_iter(args);
+    let mut opt = Options::from_iter(args);
+    if opt.jobs == 0 {
+        bail!("`--jobs` requires at least one job");
+    }
+    let config = Config::load()?;
+    opt.apply_config(&config);
+    let exclusions = MutationExclusions::new(&config.exclude)?;
+    let score_thresholds = ScoreThresholds::new(
+        opt.min_score,
+        cli_flag(opt.ignore_not_covered, opt.no_ignore_not_covered),
+        &config.score,
+    )?;
+    let timeout_policy = TimeoutPolicy::new(&opt.timeout, &config.timeout)?.with_jobs(opt.jobs);
+    let resource_limits = ResourceLimits::new(&opt.limits, &config.limits)?;
+    if opt.doctests && resource_limits.is_limited() {
+        bail!("resource limits cannot be combined with `--doctests`, since they would also limit the compilation of the doctests");
+    }
+    let baseline = opt.baseline.as_deref().map(read_json_report).transpose()?;
+
+    if let Some(Subcommand::Merge { reports }) = &opt.command {
+        return merge(reports, &score_thresholds, baseline.as_ref(), &opt);
+    }
 
     // build the testsuites and collect mutations
-    let test_bins = compile_tests(&opt)?;
+    let nextest_build = match opt.runner.unwrap_or_default() {
+        TestRunner::Libtest => None,
+        TestRunner::Nextest => Some(NextestBuild::build(&nextest_args(&opt), &opt.test_args)?),
+    };
+    let test_bins = match &nextest_build {
+        Some(build) => build.binaries.clone(),
+        None => compile_tests(&opt)?,
+    };
     if test_bins.is_empty() {
         bail!("no test executable(s) found");
     }
     let mutations = read_mutations()?;
     let num_mutations = mutations.len();
 
+    // entries are stale if they do not match any mutation, including the mutations of other shards
+    let suppressions = Suppressions::load()?;
+    let stale_suppressions = suppressions
+        .stale_entries(&mutations)
+        .into_iter()
+        .map(|s| format!("no mutation matches the entry {} in mutagen-ignore", s))
+        .collect::<Vec<_>>();
+
+    // mutations of other shards are not part of the report
+    let mutations = match opt.shard {
+        Some(shard) => mutations
+            .into_iter()
+            .filter(|m| shard.contains(m.id()))
+            .collect(),
+        None => mutations,
+    };
+    let (ignored, mutations): (Vec<_>, Vec<_>) = mutations
+        .into_iter()
+        .partition(|m| suppressions.is_ignored(m));
+    let (mutations, skipped) = select_mutations(mutations, &exclusions, &opt)?;
+
+    let doctest_args = cargo_build_args(&opt);
+    let mut test_suites = match &nextest_build {
+        // nextest runs all test-binaries of its build at once
+        Some(build) => vec![TestSuite::Nextest(build)],
+        None => test_bins.iter().map(|e| TestSuite::Bin(e)).collect(),
+    };
+    if opt.doctests {
+        test_suites.push(TestSuite::Doctests(&doctest_args));
+    }
+
     let mut progress = Progress::new(mutations.len());
-    progress.summary_compile(mutations.len(), test_bins.len())?;
+    progress.summary_compile(num_mutations, test_suites.len())?;
+    for warning in &stale_suppressions {
+        progress.warning(warning)?;
+    }
+    if mutations.len() < num_mutations {
+        progress.summary_selected(num_mutations)?;
+    }
 
     // run all test-binaries without mutations and collect coverge
-    progress.section_testsuite_unmutated(test_bins.len())?;
+    progress.section_testsuite_unmutated(test_suites.len())?;
 
-    let test_bins = test_bins
-        .iter()
+    let test_bins = test_suites
+        .into_iter()
         .enumerate()
-        .map(|(i, e)| TestBin::new(e, i))
+        .map(|(i, suite)| TestBin::new(suite, i, &opt.test_args))
         .filter_map(|bin| {
-            bin.run_test(&mut progress, &mutations)
+            bin.run_test(&mut progress, num_mutations, &mutations, &timeout_policy)
                 .map(|bin| Some(bin).filter(|bin| bin.coveres_any_mutation()))
                 .transpose()
         })
@@ -81,77 +292,434 @@ fn run() -> Result<()> {
     let coverage = CoverageCollection::merge(num_mutations, test_bins.iter().map(|b| &b.coverage));
     progress.summary_testsuite_unmutated(coverage.num_covered())?;
 
+    let journal = Journal::open(&test_bins, opt.resume)?;
+    if opt.resume {
+        if journal.is_resumed() {
+            progress.summary_resumed(journal.num_decided())?;
+        } else {
+            progress.summary_not_resumed()?;
+        }
+    }
+    let cache = if opt.no_cache {
+        None
+    } else {
+        // the results of the mutants also depend on these settings, e.g. a larger timeout might not time out
+        let settings = format!(
+            "{:?} {:?} {:?} coverage={}",
+            timeout_policy,
+            resource_limits,
+            opt.runner.unwrap_or_default(),
+            opt.coverage
+        );
+        Some(MutationCache::open(&test_bins, settings)?)
+    };
+    let mut earlier_results = EarlierResults { journal, cache };
+    let checker = MutantChecker {
+        test_bins: &test_bins,
+        kill_history: KillHistory::open()?,
+        timeout_policy: &timeout_policy,
+        resource_limits: &resource_limits,
+        opt: &opt,
+    };
+
     // run the mutations on the test-suites
     progress.section_mutants()?;
-    let mutagen_report = run_mutations(&mut progress, &test_bins, mutations, &coverage)?;
+    let mut mutagen_report = if opt.jobs == 1 {
+        run_mutations(
+            &mut progress,
+            &checker,
+            mutations,
+            &coverage,
+            &mut earlier_results,
+        )?
+    } else {
+        run_mutations_parallel(
+            &mut progress,
+            &checker,
+            opt.jobs,
+            mutations,
+            &coverage,
+            &mut earlier_results,
+        )?
+    };
+    if let Some(cache) = earlier_results.cache {
+        cache.finish()?;
+    }
+    for m in skipped {
+        mutagen_report.add_mutation_result(m, MutantStatus::Skipped);
+    }
+    for m in ignored {
+        mutagen_report.add_mutation_result(m, MutantStatus::Ignored);
+    }
 
     progress.section_summary()?;
 
     // final report
+    let json_report = write_reports(&mutagen_report, &opt)?;
+    let num_newly_survived = compare_with_baseline(baseline.as_ref(), &json_report);
+
+    progress.finish(mutagen_start.elapsed())?;
+
+    check_results(&mutagen_report, &score_thresholds, num_newly_survived)
+}
+
+/// merge the reports of several shards and write the combined report
+fn merge(
+    reports: &[PathBuf],
+    score_thresholds: &ScoreThresholds,
+    baseline: Option<&JsonReport>,
+    opt: &Options,
+) -> Result<()> {
+    let reports = reports
+        .iter()
+        .map(|path| read_json_report(path))
+        .collect::<Result<Vec<_>>>()?;
+    let mutagen_report = merge_reports(reports)?;
+
+    let json_report = write_reports(&mutagen_report, opt)?;
+    let num_newly_survived = compare_with_baseline(baseline, &json_report);
+
+    check_results(&mutagen_report, score_thresholds, num_newly_survived)
+}
+
+/// print the survived mutants and the summary and write the report files
+///
+/// Returns the json report that has been written to `target/mutagen/report.json`.
+fn write_reports(mutagen_report: &MutagenReport, opt: &Options) -> Result<JsonReport> {
     mutagen_report.print_survived();
+    if opt.show_diff {
+        print_survived_diffs(mutagen_report)?;
+    }
+    mutagen_report.print_unviable();
     mutagen_report.summary().print();
+    let json_report = write_json_report(mutagen_report)?;
+    for format in &opt.report {
+        let report_path = format.write(mutagen_report)?;
+        println!("Report written to {}", report_path.display());
+    }
+    Ok(json_report)
+}
 
-    progress.finish(mutagen_start.elapsed())?;
+/// print the differences to the baseline report, if any
+///
+/// Returns the number of mutants that newly survived.
+fn compare_with_baseline(baseline: Option<&JsonReport>, json_report: &JsonReport) -> usize {
+    match baseline {
+        Some(baseline) => {
+            let comparison = BaselineComparison::new(baseline, json_report);
+            comparison.print();
+            comparison.newly_survived.len()
+        }
+        None => 0,
+    }
+}
 
+/// fail if the mutation score is below any of the thresholds or mutants survived that have been killed in the baseline
+fn check_results(
+    mutagen_report: &MutagenReport,
+    score_thresholds: &ScoreThresholds,
+    num_newly_survived: usize,
+) -> Result<()> {
+    let mut failures = score_thresholds.check(mutagen_report);
+    if num_newly_survived > 0 {
+        failures.push(format!(
+            "{} mutants survived that have been killed in the baseline or are new",
+            num_newly_survived
+        ));
+    }
+    if !failures.is_empty() {
+        bail!("{}", failures.join("\n"));
+    }
     Ok(())
 }
 
+/// splits the mutations into the ones selected to be tested and the ones to skip
+fn select_mutations(
+    mutations: Vec<BakedMutation>,
+    exclusions: &MutationExclusions,
+    opt: &Options,
+) -> Result<(Vec<BakedMutation>, Vec<BakedMutation>)> {
+    let workspace_root = comm::workspace_root()?;
+    let current_dir = env::current_dir()?;
+    let changed_lines = opt.diff.as_deref().map(ChangedLines::since).transpose()?;
+
+    Ok(mutations.into_iter().partition(|m| {
+        opt.filter.matches(m, &workspace_root, &current_dir)
+            && !exclusions.excludes(m)
+            && changed_lines
+                .as_ref()
+                .map(|c| c.contains_mutation(m, &workspace_root))
+                .unwrap_or(true)
+    }))
+}
+
 /// run all mutations on all test-executables
 fn run_mutations(
     progress: &mut Progress,
-    test_bins: &[TestBinTested],
+    checker: &impl CheckMutant,
     mutations: Vec<BakedMutation>,
     coverage: &CoverageCollection,
+    earlier_results: &mut EarlierResults,
 ) -> Result<MutagenReport> {
     let mut mutagen_report = MutagenReport::new();
 
     for m in mutations {
-        let mutant_status = if coverage.is_covered(m.id()) {
+        let (mutant_status, kill_details) = if !coverage.is_covered(m.id()) {
+            progress.skip_mutation_uncovered(&m)?;
+            (MutantStatus::NotCovered, None)
+        } else if let Some(result) = earlier_results.get(&m, progress)? {
+            result
+        } else {
             progress.start_mutation_covered(&m)?;
+            let (mutant_status, kill_details) = checker.check_mutant(&m)?;
+            progress.finish_mutation(mutant_status)?;
+            earlier_results.insert(&m, mutant_status, kill_details.as_ref())?;
+
+            (mutant_status, kill_details)
+        };
+        add_mutation_result(&mut mutagen_report, m, mutant_status, kill_details);
+    }
+
+    Ok(mutagen_report)
+}
+
+/// run all mutations on all test-executables, testing up to `jobs` mutants at the same time.
+///
+/// The uncovered and cached mutations are logged first, the tested ones in the order they finish.
+/// The report is the same as the one of `run_mutations`.
+fn run_mutations_parallel(
+    progress: &mut Progress,
+    checker: &impl CheckMutant,
+    jobs: usize,
+    mutations: Vec<BakedMutation>,
+    coverage: &CoverageCollection,
+    earlier_results: &mut EarlierResults,
+) -> Result<MutagenReport> {
+    let mut mutagen_report = MutagenReport::new();
 
-            // run all test binaries
-            let mut mutant_status = MutantStatus::Survived;
-            for bin in test_bins {
-                mutant_status = bin.check_mutant(&m)?;
-                if mutant_status != MutantStatus::Survived {
+    let (covered, uncovered): (Vec<_>, Vec<_>) = mutations
+        .into_iter()
+        .partition(|m| coverage.is_covered(m.id()));
+
+    for m in uncovered {
+        progress.skip_mutation_uncovered(&m)?;
+        mutagen_report.add_mutation_result(m, MutantStatus::NotCovered);
+    }
+
+    let mut untested = vec![];
+    for m in covered {
+        if let Some((mutant_status, kill_details)) = earlier_results.get(&m, progress)? {
+            add_mutation_result(&mut mutagen_report, m, mutant_status, kill_details);
+        } else {
+            untested.push(m);
+        }
+    }
+
+    let queue = Mutex::new(untested.into_iter());
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|s| {
+        for _ in 0..jobs {
+            let sender = sender.clone();
+            let queue = &queue;
+            s.spawn(move || loop {
+                let m = match queue.lock().unwrap().next() {
+                    Some(m) => m,
+                    None => break,
+                };
+                let result = checker.check_mutant(&m);
+                // the receiver is gone if an error occurred in another job
+                if sender.send((m, result)).is_err() {
                     break;
                 }
-            }
-            progress.finish_mutation(mutant_status)?;
+            });
+        }
+        drop(sender);
 
-            mutant_status
-        } else {
-            progress.skip_mutation_uncovered(&m)?;
-            MutantStatus::NotCovered
+        for (m, result) in receiver {
+            let (mutant_status, kill_details) = result?;
+            progress.mutation_covered_finished(&m, mutant_status)?;
+            earlier_results.insert(&m, mutant_status, kill_details.as_ref())?;
+            add_mutation_result(&mut mutagen_report, m, mutant_status, kill_details);
+        }
+        Ok(())
+    })
+    .map(|()| mutagen_report)
+}
+
+/// add the result of a mutation and the details about its killing to the report
+fn add_mutation_result(
+    mutagen_report: &mut MutagenReport,
+    m: BakedMutation,
+    mutant_status: MutantStatus,
+    kill_details: Option<KillDetails>,
+) {
+    if let Some(kill_details) = kill_details {
+        mutagen_report.add_kill_details(m.id(), kill_details);
+    }
+    mutagen_report.add_mutation_result(m, mutant_status);
+}
+
+/// results of earlier runs that can be reused
+///
+/// The results of this run are added to the journal and the cache.
+struct EarlierResults {
+    journal: Journal,
+    cache: Option<MutationCache>,
+}
+
+impl EarlierResults {
+    /// look up the result of a mutation from the resumed run or from the cache and log it
+    fn get(
+        &mut self,
+        m: &BakedMutation,
+        progress: &mut Progress,
+    ) -> Result<Option<(MutantStatus, Option<KillDetails>)>> {
+        if let Some((mutant_status, kill_details)) = self.journal.get(m.id()) {
+            progress.skip_mutation_resumed(m, mutant_status)?;
+            if let Some(cache) = &mut self.cache {
+                cache.insert(m, mutant_status, kill_details.as_ref())?;
+            }
+            return Ok(Some((mutant_status, kill_details)));
+        }
+        let cached = match &mut self.cache {
+            Some(cache) => cache.get(m)?,
+            None => None,
         };
-        mutagen_report.add_mutation_result(m, mutant_status);
+        if let Some((mutant_status, kill_details)) = &cached {
+            progress.skip_mutation_cached(m, *mutant_status)?;
+            self.journal
+                .insert(m.id(), *mutant_status, kill_details.as_ref())?;
+        }
+        Ok(cached)
     }
 
-    Ok(mutagen_report)
+    /// record the result of a tested mutation
+    fn insert(
+        &mut self,
+        m: &BakedMutation,
+        mutant_status: MutantStatus,
+        kill_details: Option<&KillDetails>,
+    ) -> Result<()> {
+        self.journal.insert(m.id(), mutant_status, kill_details)?;
+        if let Some(cache) = &mut self.cache {
+            cache.insert(m, mutant_status, kill_details)?;
+        }
+        Ok(())
+    }
 }
 
-/// build all tests and collect test-suite executables
-fn compile_tests(opt: &Options) -> Result<Vec<PathBuf>> {
-    let mut tests: Vec<PathBuf> = Vec::new();
+/// the test suites and the state shared by all jobs testing mutants
+struct MutantChecker<'b, 'a> {
+    test_bins: &'b [TestBinTested<'a>],
+    kill_history: KillHistory,
+    timeout_policy: &'b TimeoutPolicy,
+    resource_limits: &'b ResourceLimits,
+    opt: &'b Options,
+}
+
+/// tests a single mutant
+trait CheckMutant: Sync {
+    fn check_mutant(&self, m: &BakedMutation) -> Result<(MutantStatus, Option<KillDetails>)>;
+}
+
+impl CheckMutant for MutantChecker<'_, '_> {
+    /// run all test binaries on a single mutation until the mutant is killed
+    ///
+    /// The test binaries are run in the order given by the kills of earlier mutants.
+    /// If the timeout policy says so, a test binary that timed out is run a second time to confirm the timeout.
+    fn check_mutant(&self, m: &BakedMutation) -> Result<(MutantStatus, Option<KillDetails>)> {
+        let coverage = self.opt.coverage;
+        let limits = self.resource_limits;
+        let mut result = (MutantStatus::Survived, None);
+        for (bin, first_tests) in self.kill_history.order(m, self.test_bins) {
+            result = bin.check_mutant(m, coverage, &first_tests, limits)?;
+            if result.0 == MutantStatus::Timeout && self.timeout_policy.retry_timeouts() {
+                result = bin.check_mutant(m, coverage, &first_tests, limits)?;
+            }
+            if result.0.killed() {
+                self.kill_history
+                    .insert(m, &bin.name(), result.1.as_ref())?;
+            }
+            if result.0 != MutantStatus::Survived {
+                break;
+            }
+        }
+        Ok(result)
+    }
+}
 
-    let mut feature_args: Vec<&str> = vec![];
+/// arguments of `cargo test` that select the features, packages and profile of the build
+///
+/// These are used both for building the test-binaries and for running the doctests.
+fn cargo_build_args(opt: &Options) -> Vec<&OsStr> {
+    let mut cargo_args: Vec<&OsStr> = vec![];
     if let Some(f) = &opt.features {
-        feature_args.extend(&["--features", f]);
+        cargo_args.extend(&["--features".as_ref(), f.as_ref()]);
     }
     if opt.all_features {
-        feature_args.push("--all-features");
+        cargo_args.push("--all-features".as_ref());
+    }
+    if opt.no_default_features {
+        cargo_args.push("--no-default-features".as_ref());
     }
     if let Some(p) = &opt.package {
-        feature_args.extend(&["--package", p]);
+        cargo_args.extend(&["--package".as_ref(), p.as_ref()]);
     }
     if opt.workspace {
-        feature_args.push("--workspace");
+        cargo_args.push("--workspace".as_ref());
+    }
+    if opt.release {
+        cargo_args.push("--release".as_ref());
+    }
+    if let Some(p) = &opt.profile {
+        cargo_args.extend(&["--profile".as_ref(), p.as_ref()]);
     }
+    if let Some(d) = &opt.target_dir {
+        cargo_args.extend(&["--target-dir".as_ref(), d.as_ref()]);
+    }
+    cargo_args
+}
+
+/// arguments of `cargo test` that select the features, packages and profile of the build as well as the test targets
+fn cargo_test_args(opt: &Options) -> Vec<&OsStr> {
+    let mut cargo_args = cargo_build_args(opt);
+    if opt.lib {
+        cargo_args.push("--lib".as_ref());
+    }
+    for t in &opt.tests {
+        cargo_args.extend(&["--test".as_ref(), t.as_ref()]);
+    }
+    if opt.bins {
+        cargo_args.push("--bins".as_ref());
+    }
+    cargo_args
+}
+
+/// arguments of `cargo nextest list`, which calls the cargo profile `--cargo-profile`
+fn nextest_args(opt: &Options) -> Vec<&OsStr> {
+    cargo_test_args(opt)
+        .into_iter()
+        .map(|arg| {
+            if arg == "--profile" {
+                "--cargo-profile".as_ref()
+            } else {
+                arg
+            }
+        })
+        .collect()
+}
+
+/// build all tests and collect test-suite executables
+fn compile_tests(opt: &Options) -> Result<Vec<PathBuf>> {
+    let mut tests: Vec<PathBuf> = Vec::new();
+
+    let cargo_args = cargo_test_args(opt);
 
     // execute `cargo test --no-run --message-format=json` and collect output
     let compile_out = Command::new("cargo")
         .args(&["test", "--no-run", "--message-format=json"])
-        .args(&feature_args)
+        .args(&cargo_args)
         .stderr(Stdio::inherit())
         .output()?;
     if !compile_out.status.success() {
@@ -191,6 +759,14 @@ fn compile_tests(opt: &Options) -> Result<Vec<PathBuf>> {
     Ok(tests)
 }
 
+/// write the report to `target/mutagen/report.json`
+fn write_json_report(mutagen_report: &MutagenReport) -> Result<JsonReport> {
+    let json_report = JsonReport::new(mutagen_report);
+    let report_writer = BufWriter::new(File::create(comm::get_report_file_json()?)?);
+    serde_json::to_writer_pretty(report_writer, &json_report)?;
+    Ok(json_report)
+}
+
 /// read all mutations from the given file
 ///
 /// This functions gets the file that describes all mutations performed on the target program and ensures that it exists.
@@ -216,3 +792,222 @@ fn read_mutations() -> Result<Vec<BakedMutation>> {
 
     Ok(mutations)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(args: &[&str]) -> Options {
+        Options::from_iter(std::iter::once("mutagen").chain(args.iter().copied()))
+    }
+
+    fn build_config(all_features: bool, workspace: bool) -> Config {
+        Config {
+            build: BuildConfig {
+                all_features,
+                workspace,
+                ..BuildConfig::default()
+            },
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn config_applies_without_flags() {
+        let mut opt = options(&[]);
+        opt.apply_config(&build_config(true, true));
+
+        assert!(opt.all_features);
+        assert!(opt.workspace);
+    }
+
+    #[test]
+    fn no_flags_override_config() {
+        let mut opt = options(&["--no-all-features", "--no-workspace"]);
+        opt.apply_config(&build_config(true, true));
+
+        assert!(!opt.all_features);
+        assert!(!opt.workspace);
+    }
+
+    #[test]
+    fn flags_override_config() {
+        let mut opt = options(&["--all-features", "--workspace"]);
+        opt.apply_config(&build_config(false, false));
+
+        assert!(opt.all_features);
+        assert!(opt.workspace);
+    }
+
+    #[test]
+    fn last_of_flag_and_no_flag_wins() {
+        let mut opt = options(&[
+            "--no-all-features",
+            "--all-features",
+            "--no-ignore-not-covered",
+        ]);
+        opt.apply_config(&build_config(false, false));
+
+        assert!(opt.all_features);
+        assert_eq!(
+            cli_flag(opt.ignore_not_covered, opt.no_ignore_not_covered),
+            Some(false)
+        );
+    }
+
+    use mutagen_core::comm::{CoverageHit, Mutation};
+
+    #[test]
+    fn reject_min_score_outside_of_percent_range() {
+        let parse = |min_score| Options::from_iter_safe(&["mutagen", "--min-score", min_score]);
+
+        assert_eq!(parse("75.5").unwrap().min_score, Some(75.5));
+        assert!(parse("150").is_err());
+        assert!(parse("-1").is_err());
+        assert!(parse("NaN").is_err());
+    }
+
+    #[test]
+    fn no_retry_timeouts_overrides_config() {
+        let config = TimeoutConfig {
+            retry: true,
+            ..TimeoutConfig::default()
+        };
+        let policy = |args| TimeoutPolicy::new(&options(args).timeout, &config).unwrap();
+
+        assert!(policy(&[]).retry_timeouts());
+        assert!(!policy(&["--no-retry-timeouts"]).retry_timeouts());
+        assert!(policy(&["--no-retry-timeouts", "--retry-timeouts"]).retry_timeouts());
+    }
+
+    fn os_args<'a>(args: &[&'a str]) -> Vec<&'a OsStr> {
+        args.iter().map(|arg| OsStr::new(*arg)).collect()
+    }
+
+    #[test]
+    fn cargo_test_args_of_features_and_targets() {
+        let opt = options(&[
+            "--features",
+            "a b",
+            "--no-default-features",
+            "--package",
+            "foo",
+            "--test",
+            "integration",
+            "--profile",
+            "ci",
+        ]);
+
+        assert_eq!(
+            cargo_test_args(&opt),
+            os_args(&[
+                "--features",
+                "a b",
+                "--no-default-features",
+                "--package",
+                "foo",
+                "--profile",
+                "ci",
+                "--test",
+                "integration",
+            ])
+        );
+        assert_eq!(
+            nextest_args(&opt),
+            os_args(&[
+                "--features",
+                "a b",
+                "--no-default-features",
+                "--package",
+                "foo",
+                "--cargo-profile",
+                "ci",
+                "--test",
+                "integration",
+            ])
+        );
+    }
+
+    #[test]
+    fn cargo_test_args_of_all_features_and_workspace() {
+        let opt = options(&["--all-features", "--workspace", "--lib", "--bins"]);
+        let expected = os_args(&["--all-features", "--workspace", "--lib", "--bins"]);
+
+        assert_eq!(cargo_test_args(&opt), expected);
+        assert_eq!(nextest_args(&opt), expected);
+    }
+
+    /// checker with a fixed result per mutant, which finishes the mutants with higher ids first
+    struct StubChecker;
+
+    impl CheckMutant for StubChecker {
+        fn check_mutant(&self, m: &BakedMutation) -> Result<(MutantStatus, Option<KillDetails>)> {
+            thread::sleep(std::time::Duration::from_millis(5 * (10 - m.id() as u64)));
+            let status = match m.id() % 3 {
+                0 => MutantStatus::Survived,
+                1 => MutantStatus::Killed(Some(101)),
+                _ => MutantStatus::Timeout,
+            };
+            Ok((status, None))
+        }
+    }
+
+    fn run_stub_mutations(name: &str, jobs: usize) -> String {
+        let dir = env::temp_dir().join(format!("mutagen-run-{}-{}", process::id(), name));
+        std::fs::create_dir_all(&dir).unwrap();
+        let journal = Journal::open_at(&dir.join("journal"), "stub".to_owned(), false).unwrap();
+        let mut earlier_results = EarlierResults {
+            journal,
+            cache: None,
+        };
+        let mutations = (1..10)
+            .map(|id| Mutation::new_stub().with_id(id, id))
+            .collect::<Vec<_>>();
+        // the first mutation is not covered
+        let hits = (2..10)
+            .map(|mutator_id| CoverageHit {
+                mutator_id,
+                test_name: Some("test".to_owned()),
+            })
+            .collect::<Vec<_>>();
+        let coverage = CoverageCollection::from_coverage_hits(9, &hits, &mutations);
+        let mut progress = Progress::new(mutations.len());
+
+        let report = if jobs == 1 {
+            run_mutations(
+                &mut progress,
+                &StubChecker,
+                mutations,
+                &coverage,
+                &mut earlier_results,
+            )
+        } else {
+            run_mutations_parallel(
+                &mut progress,
+                &StubChecker,
+                jobs,
+                mutations,
+                &coverage,
+                &mut earlier_results,
+            )
+        }
+        .unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let ids = report
+            .mutant_results()
+            .iter()
+            .map(|(m, _)| m.id())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, (1..10).collect::<Vec<_>>());
+        serde_json::to_string(&JsonReport::new(&report)).unwrap()
+    }
+
+    #[test]
+    fn parallel_run_reports_like_serial_run() {
+        assert_eq!(
+            run_stub_mutations("parallel", 4),
+            run_stub_mutations("serial", 1)
+        );
+    }
+}
@@ -0,0 +1,367 @@
+//! Support for cargo-nextest as backend that runs the tests.
+//!
+//! The test-binaries are built only once by `cargo nextest list`, which also writes the metadata of the build.
+//! Each run of the tests reuses this build via `--binaries-metadata` and `--cargo-metadata`, without invoking cargo again.
+//! The results of a run are read from the machine-readable output of nextest in the format `libtest-json-plus`.
+//! The arguments for the test-binaries are translated to the arguments of `cargo nextest run`.
+
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::str::{self, FromStr};
+#[cfg(unix)]
+use std::time::Duration;
+
+#[cfg(unix)]
+use wait_timeout::ChildExt;
+
+use mutagen_core::comm::{self, KillDetails};
+
+use super::test_output::panic_message;
+
+/// time nextest gets to terminate its running tests before it is killed.
+///
+/// Nextest itself waits 10 seconds for the tests to exit after asking them to terminate.
+#[cfg(unix)]
+const TERMINATE_GRACE_PERIOD: Duration = Duration::from_secs(15);
+
+/// The backend that runs the test suites, selected via the option `--runner`
+#[derive(Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TestRunner {
+    /// the test-binaries built by `cargo test`, using the default test harness
+    #[default]
+    Libtest,
+    /// cargo-nextest, which runs each test in its own process
+    Nextest,
+}
+
+impl TestRunner {
+    /// names of all runners as used on the command line
+    pub const NAMES: &'static [&'static str] = &["libtest", "nextest"];
+}
+
+impl FromStr for TestRunner {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "libtest" => Ok(Self::Libtest),
+            "nextest" => Ok(Self::Nextest),
+            _ => bail!("unknown test runner `{}`", s),
+        }
+    }
+}
+
+/// The test-binaries built by cargo-nextest, together with the metadata to reuse the build
+#[derive(Debug)]
+pub struct NextestBuild {
+    /// paths of all test-binaries of the build
+    pub binaries: Vec<PathBuf>,
+    binaries_metadata: PathBuf,
+    cargo_metadata: PathBuf,
+    test_args: NextestTestArgs,
+}
+
+impl NextestBuild {
+    /// Builds all test-binaries with `cargo nextest list`, passing the given arguments to cargo.
+    ///
+    /// The arguments for the test-binaries are translated for nextest before building, such that unsupported arguments are rejected early.
+    pub fn build(cargo_args: &[&OsStr], test_args: &[String]) -> Result<Self> {
+        let test_args = translate_test_args(test_args)?;
+        let list_out = Command::new("cargo")
+            .args(["nextest", "list", "--list-type", "binaries-only"])
+            .args(["--message-format", "json"])
+            .args(cargo_args)
+            .stderr(Stdio::inherit())
+            .output()?;
+        if !list_out.status.success() {
+            bail!(
+                "`cargo nextest list` returned non-zero exit status, is cargo-nextest installed?"
+            );
+        }
+        let binaries = binary_paths(str::from_utf8(&list_out.stdout)?)?;
+        let binaries_metadata = comm::get_nextest_binaries_file()?;
+        write_file(&binaries_metadata, &list_out.stdout)?;
+
+        let metadata_out = Command::new("cargo")
+            .args(["metadata", "--format-version", "1", "--no-deps"])
+            .stderr(Stdio::inherit())
+            .output()?;
+        if !metadata_out.status.success() {
+            bail!("`cargo metadata` returned non-zero exit status");
+        }
+        let cargo_metadata = comm::get_nextest_cargo_metadata_file()?;
+        write_file(&cargo_metadata, &metadata_out.stdout)?;
+
+        Ok(Self {
+            binaries,
+            binaries_metadata,
+            cargo_metadata,
+            test_args,
+        })
+    }
+
+    /// Creates the command that runs the tests of the build with `cargo nextest run`, including the translated test arguments.
+    ///
+    /// Further arguments are interpreted by nextest like the arguments of a libtest test-binary, e.g. test names and `--exact`.
+    pub fn command(&self) -> Command {
+        let mut command = Command::new("cargo");
+        command
+            .args(["nextest", "run", "--binaries-metadata"])
+            .arg(&self.binaries_metadata)
+            .arg("--cargo-metadata")
+            .arg(&self.cargo_metadata)
+            .args(["--message-format", "libtest-json-plus"])
+            .args(&self.test_args.options)
+            .arg("--")
+            .args(&self.test_args.filters);
+        // the machine-readable output is still experimental in nextest
+        command.env("NEXTEST_EXPERIMENTAL_LIBTEST_JSON", "1");
+        // nextest reports its progress on stderr
+        command.stderr(Stdio::null());
+        command
+    }
+}
+
+/// Arguments of `cargo nextest run`, translated from the arguments for libtest test-binaries
+#[derive(Debug, Default, PartialEq, Eq)]
+struct NextestTestArgs {
+    /// options of nextest, given before `--`
+    options: Vec<String>,
+    /// test filters and the arguments that nextest accepts like a test-binary, given after `--`
+    filters: Vec<String>,
+}
+
+/// Translates the arguments for libtest test-binaries into arguments of `cargo nextest run`.
+///
+/// After `--`, nextest only accepts test filters, `--exact`, `--skip`, `--ignored` and `--include-ignored`.
+/// `--test-threads` and `--nocapture` are translated to the options of nextest, all other options are rejected.
+fn translate_test_args(test_args: &[String]) -> Result<NextestTestArgs> {
+    let mut args = NextestTestArgs::default();
+    let mut test_args = test_args.iter();
+    while let Some(arg) = test_args.next() {
+        match arg.as_str() {
+            "--test-threads" | "--skip" => {
+                let value = match test_args.next() {
+                    Some(value) => value,
+                    None => bail!("test argument `{}` requires a value", arg),
+                };
+                let translated = if arg == "--skip" {
+                    &mut args.filters
+                } else {
+                    &mut args.options
+                };
+                translated.push(arg.clone());
+                translated.push(value.clone());
+            }
+            "--nocapture" => args.options.push("--no-capture".to_owned()),
+            "--exact" | "--ignored" | "--include-ignored" => args.filters.push(arg.clone()),
+            _ if arg.starts_with("--test-threads=") => args.options.push(arg.clone()),
+            _ if arg.starts_with('-') => bail!(
+                "test argument `{}` is not supported with `--runner nextest`",
+                arg
+            ),
+            _ => args.filters.push(arg.clone()),
+        }
+    }
+    Ok(args)
+}
+
+fn write_file(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+/// list of test-binaries as written by `cargo nextest list --list-type binaries-only`
+#[derive(Deserialize)]
+struct BinaryList {
+    #[serde(rename = "rust-binaries")]
+    rust_binaries: BTreeMap<String, RustBinary>,
+}
+
+#[derive(Deserialize)]
+struct RustBinary {
+    #[serde(rename = "binary-path")]
+    binary_path: PathBuf,
+}
+
+/// extracts the paths of the test-binaries from the output of `cargo nextest list`
+fn binary_paths(binary_list: &str) -> Result<Vec<PathBuf>> {
+    let binary_list: BinaryList = serde_json::from_str(binary_list)?;
+    Ok(binary_list
+        .rust_binaries
+        .into_values()
+        .map(|b| b.binary_path)
+        .collect())
+}
+
+/// single line of the output of nextest in the format `libtest-json-plus`
+#[derive(Deserialize)]
+struct TestEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    event: String,
+    #[serde(default)]
+    name: String,
+    stdout: Option<String>,
+}
+
+/// Extracts the names of the failed tests and the first panic message from the output of nextest.
+///
+/// Nextest prefixes the name of each test with the id of its test-binary, which is removed.
+pub fn parse_nextest_kill_details(stdout: &str) -> KillDetails {
+    let mut failed_tests = vec![];
+    let mut panic = None;
+    for line in stdout.lines() {
+        // nextest may print lines that are not part of the machine-readable output
+        let event = match serde_json::from_str::<TestEvent>(line) {
+            Ok(event) => event,
+            Err(_) => continue,
+        };
+        if event.kind != "test" || event.event != "failed" {
+            continue;
+        }
+        let name = match event.name.split_once('$') {
+            Some((_, name)) => name,
+            None => &event.name,
+        };
+        failed_tests.push(name.to_owned());
+        if panic.is_none() {
+            panic = event.stdout.as_deref().and_then(panic_message);
+        }
+    }
+    KillDetails {
+        failed_tests,
+        panic_message: panic,
+    }
+}
+
+/// Stops a run of nextest that exceeded its timeout.
+///
+/// Killing nextest would leave its test processes running, so nextest is asked to terminate the tests first.
+/// It is killed only if it does not exit within the grace period.
+pub fn terminate(child: &mut Child) -> Result<()> {
+    #[cfg(unix)]
+    {
+        // the child has not been waited for yet, so its pid cannot have been reused
+        unsafe {
+            libc::kill(child.id() as libc::pid_t, libc::SIGTERM);
+        }
+        if child.wait_timeout(TERMINATE_GRACE_PERIOD)?.is_some() {
+            return Ok(());
+        }
+    }
+    child.kill()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runner_from_str() {
+        assert_eq!(
+            "libtest".parse::<TestRunner>().unwrap(),
+            TestRunner::Libtest
+        );
+        assert_eq!(
+            "nextest".parse::<TestRunner>().unwrap(),
+            TestRunner::Nextest
+        );
+        assert!("cargo".parse::<TestRunner>().is_err());
+    }
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn translate_libtest_args() {
+        let translated = translate_test_args(&args(&[
+            "parser",
+            "--test-threads",
+            "2",
+            "--skip",
+            "slow",
+            "--nocapture",
+            "--include-ignored",
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            translated.options,
+            args(&["--test-threads", "2", "--no-capture"])
+        );
+        assert_eq!(
+            translated.filters,
+            args(&["parser", "--skip", "slow", "--include-ignored"])
+        );
+        assert_eq!(
+            translate_test_args(&args(&["--test-threads=4"]))
+                .unwrap()
+                .options,
+            args(&["--test-threads=4"])
+        );
+    }
+
+    #[test]
+    fn reject_unsupported_libtest_args() {
+        assert!(translate_test_args(&args(&["--show-output"])).is_err());
+        assert!(translate_test_args(&args(&["--format", "json"])).is_err());
+        assert!(translate_test_args(&args(&["--test-threads"])).is_err());
+    }
+
+    #[test]
+    fn parse_binary_list() {
+        let binary_list = r#"{
+            "rust-build-meta": { "target-directory": "/ws/target" },
+            "rust-binaries": {
+                "foo": {
+                    "binary-id": "foo",
+                    "binary-path": "/ws/target/debug/deps/foo-0123",
+                    "kind": "lib"
+                },
+                "foo::integration": {
+                    "binary-id": "foo::integration",
+                    "binary-path": "/ws/target/debug/deps/integration-4567",
+                    "kind": "test"
+                }
+            }
+        }"#;
+
+        assert_eq!(
+            binary_paths(binary_list).unwrap(),
+            vec![
+                PathBuf::from("/ws/target/debug/deps/foo-0123"),
+                PathBuf::from("/ws/target/debug/deps/integration-4567"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_failed_tests() {
+        let stdout = r#"{"type":"suite","event":"started","test_count":3,"nextest":{"crate":"foo","test_binary":"foo","kind":"lib"}}
+{"type":"test","event":"started","name":"foo$tests::a"}
+{"type":"test","event":"ok","name":"foo$tests::a","exec_time":0.01}
+{"type":"test","event":"failed","name":"foo$tests::b","exec_time":0.01,"stdout":"\nrunning 1 test\nthread 'tests::b' panicked at src/lib.rs:10:9:\nexplicit panic\nnote: run with `RUST_BACKTRACE=1` environment variable to display a backtrace\n"}
+{"type":"test","event":"failed","name":"foo::integration$c","exec_time":0.01,"stdout":""}
+{"type":"suite","event":"failed","passed":1,"failed":2,"ignored":0,"measured":0,"filtered_out":0,"exec_time":0.02}
+"#;
+        let details = parse_nextest_kill_details(stdout);
+
+        assert_eq!(details.failed_tests, vec!["tests::b", "c"]);
+        assert_eq!(
+            details.panic_message.as_deref(),
+            Some("src/lib.rs:10:9:\nexplicit panic")
+        );
+    }
+}
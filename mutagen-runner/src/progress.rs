@@ -9,7 +9,6 @@
 
 use anyhow::Result;
 
-use std::path::Path;
 use std::time::Duration;
 
 use mutagen_core::comm::{BakedMutation, MutantStatus};
@@ -45,6 +44,34 @@ impl Progress {
         Ok(())
     }
 
+    /// Print how many mutants have been decided in the resumed run.
+    pub fn summary_resumed(&mut self, num_decided: usize) -> Result<()> {
+        self.bar.println(&format!(
+            "Resuming run with {} decided mutants",
+            num_decided
+        ))
+    }
+
+    /// Print that the run cannot be resumed and starts from the beginning.
+    pub fn summary_not_resumed(&mut self) -> Result<()> {
+        self.bar.println(
+            "Unable to resume: no journal for the current mutations and test executables found",
+        )
+    }
+
+    /// Print how many mutations have been selected to be tested.
+    pub fn summary_selected(&mut self, num_total: usize) -> Result<()> {
+        self.bar.println(&format!(
+            "Selected mutations: {}/{}",
+            self.num_mutations, num_total
+        ))
+    }
+
+    /// Print a warning that does not abort the run.
+    pub fn warning(&mut self, message: &str) -> Result<()> {
+        self.bar.println(&format!("warning: {}", message))
+    }
+
     /// Start the section that runs the test suites unmutated.
     pub fn section_testsuite_unmutated(&mut self, num_tests: usize) -> Result<()> {
         self.bar.println("")?;
@@ -68,15 +95,15 @@ impl Progress {
     }
 
     /// indicate the start of a run of a single testsuite without mutations
-    pub fn start_testsuite_unmutated(&mut self, bin: &Path, id: usize) -> Result<()> {
-        let log_string = format!("{} ... ", bin.display());
+    pub fn start_testsuite_unmutated(&mut self, name: &str, id: usize) -> Result<()> {
+        let log_string = format!("{} ... ", name);
         self.bar.print(log_string)?;
 
         if self.bar.shows_progress() {
             let bar = ProgressBarState {
                 action: "Run Tests",
                 current: id + 1,
-                action_details: format!("{}", bin.display()),
+                action_details: name.to_owned(),
             };
 
             self.bar.set_state(bar)?;
@@ -149,6 +176,31 @@ impl Progress {
         ))
     }
 
+    /// indicate that the result of a covered mutation has been taken from the cache.
+    pub fn skip_mutation_cached(&mut self, m: &BakedMutation, status: MutantStatus) -> Result<()> {
+        self.skip_mutation_known(m, status, "cached")
+    }
+
+    /// indicate that the result of a covered mutation has been taken from the journal of an interrupted run.
+    pub fn skip_mutation_resumed(&mut self, m: &BakedMutation, status: MutantStatus) -> Result<()> {
+        self.skip_mutation_known(m, status, "resumed")
+    }
+
+    fn skip_mutation_known(
+        &mut self,
+        m: &BakedMutation,
+        status: MutantStatus,
+        origin: &str,
+    ) -> Result<()> {
+        self.tested_mutations += 1;
+        self.bar.println(&format!(
+            "{} ... {} ({})",
+            mutation_log_string(m),
+            status,
+            origin
+        ))
+    }
+
     /// indicate that a mutation started with `start_mutation` has been finished.
     ///
     /// The status is printed and progress bar is updated
@@ -157,6 +209,35 @@ impl Progress {
         Ok(())
     }
 
+    /// indicate that a test-run of a covered mutation has been finished and display the result.
+    ///
+    /// In contrast to `start_mutation_covered` and `finish_mutation`, the mutation is logged in a single line.
+    /// This is used when several mutations are tested at the same time.
+    pub fn mutation_covered_finished(
+        &mut self,
+        m: &BakedMutation,
+        status: MutantStatus,
+    ) -> Result<()> {
+        self.tested_mutations += 1;
+
+        if self.bar.shows_progress() {
+            let action_details = format!(
+                "{}{}",
+                m.source_file().display(),
+                m.context_description_in_brackets(),
+            );
+            let bar = ProgressBarState {
+                action: "Test Mutants",
+                current: self.tested_mutations,
+                action_details,
+            };
+            self.bar.set_state(bar)?;
+        }
+
+        self.bar
+            .println(&format!("{} ... {}", mutation_log_string(m), status))
+    }
+
     /// indicate that mutation-testing is finished
     ///
     /// clears the progress-bar
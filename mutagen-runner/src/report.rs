@@ -0,0 +1,105 @@
+//! Additional report formats of the mutation analysis.
+//!
+//! Each format is written to its own location in the `target/mutagen` directory.
+
+mod html;
+mod junit;
+mod sarif;
+mod stryker;
+
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use mutagen_core::comm::{KillDetails, MutagenReport};
+
+/// A format of the report that can be selected via the option `--report`
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    /// web-based report in `target/mutagen/html`
+    Html,
+    /// JUnit XML in `target/mutagen/junit.xml`
+    Junit,
+    /// SARIF 2.1 log of survived mutants in `target/mutagen/report.sarif`
+    Sarif,
+    /// mutation-testing-elements JSON as used by Stryker in `target/mutagen/stryker.json`
+    Stryker,
+}
+
+impl ReportFormat {
+    /// names of all formats as used on the command line
+    pub const NAMES: &'static [&'static str] = &["html", "junit", "sarif", "stryker"];
+
+    /// Writes the report in this format.
+    ///
+    /// Returns the path of the main file of the written report.
+    pub fn write(self, report: &MutagenReport) -> Result<PathBuf> {
+        match self {
+            Self::Html => html::write_html_report(report),
+            Self::Junit => junit::write_junit_report(report),
+            Self::Sarif => sarif::write_sarif_report(report),
+            Self::Stryker => stryker::write_stryker_report(report),
+        }
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "html" => Ok(Self::Html),
+            "junit" => Ok(Self::Junit),
+            "sarif" => Ok(Self::Sarif),
+            "stryker" => Ok(Self::Stryker),
+            _ => bail!("unknown report format `{}`", s),
+        }
+    }
+}
+
+/// Reads the current source of a file of the report, given relative to the workspace root.
+///
+/// The source is not available, if the file has been removed since the compilation.
+/// The reports then show the mutations of this file without their source.
+pub fn read_source(workspace_root: &Path, file: &Path) -> Option<String> {
+    fs::read_to_string(workspace_root.join(file)).ok()
+}
+
+/// escapes text for use in HTML and XML documents
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// describes how a mutant has been killed, using the failed tests and panic message if known
+fn kill_description(kill_details: &KillDetails) -> String {
+    let mut description = "killed".to_owned();
+    if !kill_details.failed_tests.is_empty() {
+        description += " by ";
+        description += &kill_details.failed_tests.join(", ");
+    }
+    if let Some(panic_message) = &kill_details.panic_message {
+        description += "\npanicked at ";
+        description += panic_message;
+    }
+    description
+}
+
+/// joins the components of a path with `/`, as required for uris and in reports for other platforms
+fn slash_path(path: &Path) -> String {
+    let path = path
+        .iter()
+        .map(|c| c.to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    // the root component of absolute paths is already a `/`
+    path.strip_prefix("//")
+        .map(|p| format!("/{}", p))
+        .unwrap_or(path)
+}
@@ -0,0 +1,426 @@
+//! Web-based report of the mutation analysis.
+//!
+//! The report is a static site without any external resources.
+//! The index page lists all source files with their mutation score.
+//! For each source file, a page shows the annotated source code where survived and not covered mutations are highlighted.
+
+use anyhow::Result;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use mutagen_core::comm::{self, BakedMutation, MutagenReport, MutantStatus, ReportSummary};
+
+use super::{escape, read_source};
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; }
+table.files { border-collapse: collapse; }
+table.files td, table.files th { padding: 0.2em 1em; border-bottom: 1px solid #ccc; text-align: right; }
+table.files td:first-child, table.files th:first-child { text-align: left; }
+.source { font-family: monospace; white-space: pre; }
+.line { display: flex; }
+.lineno { display: inline-block; min-width: 4em; padding-right: 1em; text-align: right; color: #888; user-select: none; }
+.line.killed .lineno, .line.timeout .lineno, .line.limit-exceeded .lineno { background: #d4f4d4; }
+.line.survived .lineno { background: #f8c8c8; }
+.line.not-covered .lineno { background: #f8e0b0; }
+span.survived { background: #f8c8c8; text-decoration: underline wavy #c00; }
+span.not-covered { background: #f8e0b0; text-decoration: underline wavy #c80; }
+.mutations { margin: 0.2em 0 0.5em 5em; font-family: sans-serif; font-size: 0.9em; white-space: normal; }
+.mutation.killed, .mutation.timeout, .mutation.limit-exceeded { color: #060; }
+.mutation.survived { color: #a00; font-weight: bold; }
+.mutation.not-covered { color: #a60; font-weight: bold; }
+.mutation.skipped, .mutation.unviable, .mutation.ignored { color: #888; }
+";
+
+/// Writes the web-based report to `target/mutagen/html`.
+///
+/// Returns the path to the index page.
+pub fn write_html_report(report: &MutagenReport) -> Result<PathBuf> {
+    let report_dir = comm::get_report_dir_html()?;
+    fs::create_dir_all(&report_dir)?;
+    let workspace_root = comm::workspace_root()?;
+
+    let pages = report_pages(report, |file| read_source(&workspace_root, file))?;
+    for (page_name, page) in pages {
+        fs::write(report_dir.join(page_name), page)?;
+    }
+
+    Ok(report_dir.join("index.html"))
+}
+
+/// generates all pages of the report with their file names, reading the source files with the given function
+fn report_pages(
+    report: &MutagenReport,
+    read_source: impl Fn(&Path) -> Option<String>,
+) -> Result<Vec<(String, String)>> {
+    let mut pages = vec![];
+    let mut file_rows = String::new();
+    for (i, (file, mutations)) in report.mutations_per_file().into_iter().enumerate() {
+        let mut summary = ReportSummary::default();
+        for (_, s) in &mutations {
+            summary.add_mutation_result(*s);
+        }
+
+        let source = read_source(file);
+        let page_name = format!("file-{}.html", i + 1);
+
+        writeln!(
+            file_rows,
+            "<tr><td><a href=\"{}\">{}</a></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{:.2}%</td></tr>",
+            page_name,
+            escape(&file.display().to_string()),
+            summary.num_mutations(),
+            summary.killed(),
+            summary.survived() - summary.not_covered(),
+            summary.not_covered(),
+            summary.skipped(),
+            summary.mutation_score(),
+        )?;
+        pages.push((
+            page_name,
+            file_page(file, source.as_deref(), &mutations, summary)?,
+        ));
+    }
+
+    let summary = report.summary();
+    let index_body = format!(
+        "<h1>Mutation Report</h1>\n\
+         <p>{} mutants, {} killed ({} by timeout), {} survived, {} not covered. Mutation score: {:.2}%</p>\n\
+         <table class=\"files\">\n\
+         <tr><th>File</th><th>Mutants</th><th>Killed</th><th>Survived</th><th>Not Covered</th><th>Skipped</th><th>Score</th></tr>\n\
+         {}</table>",
+        summary.num_mutations(),
+        summary.killed(),
+        summary.timeout(),
+        summary.survived() - summary.not_covered(),
+        summary.not_covered(),
+        summary.mutation_score(),
+        file_rows,
+    );
+    pages.push((
+        "index.html".to_owned(),
+        html_page("Mutation Report", &index_body),
+    ));
+
+    Ok(pages)
+}
+
+/// generates the page for a single source file
+fn file_page(
+    file: &Path,
+    source: Option<&str>,
+    mutations: &[(&BakedMutation, MutantStatus)],
+    summary: ReportSummary,
+) -> Result<String> {
+    let file_name = escape(&file.display().to_string());
+    let mut body = format!(
+        "<p><a href=\"index.html\">Back to overview</a></p>\n\
+         <h1>{}</h1>\n\
+         <p>{} mutants, {} killed, {} survived, {} not covered. Mutation score: {:.2}%</p>\n",
+        file_name,
+        summary.num_mutations(),
+        summary.killed(),
+        summary.survived() - summary.not_covered(),
+        summary.not_covered(),
+        summary.mutation_score(),
+    );
+
+    let source = match source {
+        Some(source) => source,
+        None => {
+            body += "<p>The source file could not be read.</p>\n";
+            body += &mutation_list(mutations)?;
+            return Ok(html_page(&file_name, &body));
+        }
+    };
+
+    // mutations that could not be located are listed at the top
+    let unlocated = mutations
+        .iter()
+        .filter(|(m, _)| m.location().is_err())
+        .copied()
+        .collect::<Vec<_>>();
+    body += &mutation_list(&unlocated)?;
+
+    body += "<div class=\"source\">\n";
+    for (i, line) in source.lines().enumerate() {
+        let line_no = i + 1;
+        let chars = line.chars().collect::<Vec<_>>();
+
+        // the most severe status of all mutations on each character
+        let mut highlight = vec![None; chars.len()];
+        let mut line_status = None;
+        let mut starting_here = vec![];
+
+        for (m, s) in mutations {
+            let location = match m.location() {
+                Ok(location) => location,
+                Err(_) => continue,
+            };
+            if line_no < location.start_line || line_no > location.end_line {
+                continue;
+            }
+            if line_no == location.start_line {
+                starting_here.push((*m, *s));
+            }
+            line_status = max_severity(line_status, *s);
+
+            if s.survived() {
+                let start = if line_no == location.start_line {
+                    location.start_column
+                } else {
+                    0
+                };
+                let end = if line_no == location.end_line {
+                    location.end_column
+                } else {
+                    chars.len()
+                };
+                for h in highlight.iter_mut().take(end).skip(start) {
+                    *h = max_severity(*h, *s);
+                }
+            }
+        }
+
+        let line_class = line_status.map(status_class).unwrap_or("");
+        writeln!(
+            body,
+            "<div class=\"line {}\"><span class=\"lineno\">{}</span><span>{}</span></div>",
+            line_class,
+            line_no,
+            highlighted_line(&chars, &highlight)?,
+        )?;
+        body += &mutation_list(&starting_here)?;
+    }
+    body += "</div>\n";
+
+    Ok(html_page(&file_name, &body))
+}
+
+/// renders a line of source code, where characters with a given status are highlighted
+fn highlighted_line(chars: &[char], highlight: &[Option<MutantStatus>]) -> Result<String> {
+    let mut result = String::new();
+    let mut current = None;
+    for (c, h) in chars.iter().zip(highlight) {
+        if *h != current {
+            if current.is_some() {
+                result += "</span>";
+            }
+            if let Some(s) = h {
+                write!(result, "<span class=\"{}\">", status_class(*s))?;
+            }
+            current = *h;
+        }
+        result += &escape(&c.to_string());
+    }
+    if current.is_some() {
+        result += "</span>";
+    }
+    Ok(result)
+}
+
+/// renders a list of mutations with their status and description
+fn mutation_list(mutations: &[(&BakedMutation, MutantStatus)]) -> Result<String> {
+    let mut result = String::new();
+    for (m, s) in mutations {
+        writeln!(
+            result,
+            "<div class=\"mutation {}\">{}: {} ({}) at {}{}: {}</div>",
+            status_class(*s),
+            m.id(),
+            escape(&m.mutation_description()),
+            escape(m.mutator_name()),
+            escape(m.location_in_file()),
+            escape(&m.context_description_in_brackets()),
+            s,
+        )?;
+    }
+    if result.is_empty() {
+        Ok(result)
+    } else {
+        Ok(format!("<div class=\"mutations\">\n{}</div>\n", result))
+    }
+}
+
+fn html_page(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n\
+         <html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n\
+         <body>\n{}</body>\n</html>\n",
+        title, STYLE, body
+    )
+}
+
+/// css class used to display a mutant status
+fn status_class(status: MutantStatus) -> &'static str {
+    match status {
+        MutantStatus::NotCovered => "not-covered",
+        MutantStatus::Survived => "survived",
+        MutantStatus::Killed(_) => "killed",
+        MutantStatus::Timeout => "timeout",
+        MutantStatus::LimitExceeded(_) => "limit-exceeded",
+        MutantStatus::Skipped => "skipped",
+        MutantStatus::Unviable => "unviable",
+        MutantStatus::Ignored => "ignored",
+    }
+}
+
+/// returns the status that should be displayed more prominently
+fn max_severity(current: Option<MutantStatus>, status: MutantStatus) -> Option<MutantStatus> {
+    let severity = |s: MutantStatus| match s {
+        MutantStatus::NotCovered => 3,
+        MutantStatus::Survived => 2,
+        MutantStatus::Killed(_) | MutantStatus::Timeout | MutantStatus::LimitExceeded(_) => 1,
+        MutantStatus::Skipped | MutantStatus::Unviable | MutantStatus::Ignored => 0,
+    };
+    match current {
+        Some(current) if severity(current) >= severity(status) => Some(current),
+        _ => Some(status),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::mutation_with;
+
+    fn summary(mutations: &[(&BakedMutation, MutantStatus)]) -> ReportSummary {
+        let mut summary = ReportSummary::default();
+        for (_, s) in mutations {
+            summary.add_mutation_result(*s);
+        }
+        summary
+    }
+
+    #[test]
+    fn highlight_survived_span_across_lines() {
+        let source = "fn foo() {\n    let x = {\n        1\n    };\n}\n";
+        let killed = mutation_with(1, |m| m.stub_location("1:3-1:6"));
+        let survived = mutation_with(2, |m| m.stub_location("2:12-4:5"));
+        let killed_inside = mutation_with(3, |m| m.stub_location("3:8-3:9"));
+        let mutations = [
+            (&killed, MutantStatus::Killed(Some(101))),
+            (&survived, MutantStatus::Survived),
+            (&killed_inside, MutantStatus::Killed(Some(101))),
+        ];
+
+        let page = file_page(
+            Path::new("src/lib.rs"),
+            Some(source),
+            &mutations,
+            summary(&mutations),
+        )
+        .unwrap();
+
+        assert!(page.contains(
+            "<div class=\"line killed\"><span class=\"lineno\">1</span><span>fn foo() {</span></div>"
+        ));
+        assert!(page.contains(
+            "<div class=\"line survived\"><span class=\"lineno\">2</span>\
+             <span>    let x = <span class=\"survived\">{</span></span></div>"
+        ));
+        assert!(page.contains(
+            "<div class=\"line survived\"><span class=\"lineno\">3</span>\
+             <span><span class=\"survived\">        1</span></span></div>"
+        ));
+        assert!(page.contains(
+            "<div class=\"line survived\"><span class=\"lineno\">4</span>\
+             <span><span class=\"survived\">    }</span>;</span></div>"
+        ));
+        assert!(page
+            .contains("<div class=\"line \"><span class=\"lineno\">5</span><span>}</span></div>"));
+    }
+
+    #[test]
+    fn escape_source_code() {
+        let source = "if a < b && c > d {\n";
+        let m = mutation_with(1, |m| {
+            m.stub_source_file("src/<lib>.rs").stub_location("1:5-1:6")
+        });
+        let mutations = [(&m, MutantStatus::Survived)];
+
+        let page = file_page(
+            Path::new("src/<lib>.rs"),
+            Some(source),
+            &mutations,
+            summary(&mutations),
+        )
+        .unwrap();
+
+        assert!(page.contains(
+            "<span>if a <span class=\"survived\">&lt;</span> b &amp;&amp; c &gt; d {</span>"
+        ));
+        assert!(page.contains("<h1>src/&lt;lib&gt;.rs</h1>"));
+        assert!(page.contains("replace `&lt;` with `&gt;` (binop_cmp)"));
+        assert!(!page.contains("a < b"));
+    }
+
+    #[test]
+    fn list_mutations_with_invalid_location_before_source() {
+        let source = "fn foo() {}\n";
+        let located = mutation_with(1, |m| m.stub_location("1:3-1:6"));
+        let unlocated = mutation_with(2, |m| m.stub_location("stub"));
+        let mutations = [
+            (&located, MutantStatus::Killed(None)),
+            (&unlocated, MutantStatus::Survived),
+        ];
+
+        let page = file_page(
+            Path::new("src/lib.rs"),
+            Some(source),
+            &mutations,
+            summary(&mutations),
+        )
+        .unwrap();
+
+        let unlocated_entry = page.find("<div class=\"mutation survived\">2: ").unwrap();
+        let source_start = page.find("<div class=\"source\">").unwrap();
+        assert!(unlocated_entry < source_start);
+        // the line is only marked by the mutation with a valid location
+        assert!(page.contains("<div class=\"line killed\"><span class=\"lineno\">1</span>"));
+    }
+
+    #[test]
+    fn index_with_score_per_file() {
+        let mut report = MutagenReport::new();
+        report.add_mutation_result(
+            mutation_with(1, |m| m.stub_source_file("src/a.rs")),
+            MutantStatus::Killed(None),
+        );
+        report.add_mutation_result(
+            mutation_with(2, |m| m.stub_source_file("src/a.rs")),
+            MutantStatus::Survived,
+        );
+        report.add_mutation_result(
+            mutation_with(3, |m| m.stub_source_file("src/a.rs")),
+            MutantStatus::NotCovered,
+        );
+        report.add_mutation_result(
+            mutation_with(4, |m| m.stub_source_file("src/a.rs")),
+            MutantStatus::Timeout,
+        );
+        report.add_mutation_result(
+            mutation_with(5, |m| m.stub_source_file("src/b.rs")),
+            MutantStatus::Killed(None),
+        );
+
+        let pages = report_pages(&report, |_| None).unwrap();
+        let page_names = pages
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(page_names, vec!["file-1.html", "file-2.html", "index.html"]);
+
+        let index = &pages[2].1;
+        assert!(index.contains(
+            "<tr><td><a href=\"file-1.html\">src/a.rs</a></td>\
+             <td>4</td><td>2</td><td>1</td><td>1</td><td>0</td><td>50.00%</td></tr>"
+        ));
+        assert!(index.contains(
+            "<tr><td><a href=\"file-2.html\">src/b.rs</a></td>\
+             <td>1</td><td>1</td><td>0</td><td>0</td><td>0</td><td>100.00%</td></tr>"
+        ));
+        assert!(pages[0].1.contains("The source file could not be read."));
+    }
+}
@@ -0,0 +1,166 @@
+//! Report of the mutation analysis in JUnit XML format.
+//!
+//! Each mutation is a testcase, grouped into one testsuite per source file.
+//! Survived and not covered mutants are failures, skipped, unviable and ignored mutants are skipped testcases.
+//! The status of each mutant is also given as property `mutant_status` of its testcase.
+
+use anyhow::Result;
+use std::fmt::Write;
+use std::fs;
+use std::path::PathBuf;
+
+use mutagen_core::comm::{
+    self, BakedMutation, KillDetails, MutagenReport, MutantStatus, ReportSummary,
+};
+
+use super::{escape, kill_description};
+
+/// Writes the report to `target/mutagen/junit.xml`.
+pub fn write_junit_report(report: &MutagenReport) -> Result<PathBuf> {
+    let report_file = comm::get_report_file_junit()?;
+    fs::write(&report_file, junit_xml(report)?)?;
+    Ok(report_file)
+}
+
+fn junit_xml(report: &MutagenReport) -> Result<String> {
+    let mut testsuites = String::new();
+    for (file, mutations) in report.mutations_per_file() {
+        let mut summary = ReportSummary::default();
+        for (_, s) in &mutations {
+            summary.add_mutation_result(*s);
+        }
+        let file = file.display().to_string();
+        writeln!(
+            testsuites,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\">",
+            escape(&file),
+            summary.num_mutations(),
+            summary.survived(),
+            summary.num_mutations() - summary.num_tested(),
+        )?;
+        for (m, s) in mutations {
+            testsuites += &testcase(&file, m, s, report.kill_details(m.id()))?;
+        }
+        testsuites += "  </testsuite>\n";
+    }
+
+    let summary = report.summary();
+    Ok(format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <testsuites name=\"mutagen\" tests=\"{}\" failures=\"{}\" skipped=\"{}\">\n\
+         {}</testsuites>\n",
+        summary.num_mutations(),
+        summary.survived(),
+        summary.num_mutations() - summary.num_tested(),
+        testsuites,
+    ))
+}
+
+/// renders a single mutation as testcase
+fn testcase(
+    file: &str,
+    m: &BakedMutation,
+    status: MutantStatus,
+    kill_details: Option<&KillDetails>,
+) -> Result<String> {
+    let mut result = String::new();
+    writeln!(
+        result,
+        "    <testcase name=\"{}\" classname=\"{}\">",
+        escape(&format!(
+            "{}: {} at {}{}",
+            m.id(),
+            m.mutation_description(),
+            m.location_in_file(),
+            m.context_description_in_brackets()
+        )),
+        escape(file),
+    )?;
+    writeln!(
+        result,
+        "      <properties><property name=\"mutant_status\" value=\"{}\"/></properties>",
+        escape(&status.to_string()),
+    )?;
+
+    let description = format!(
+        "{} at {}@{}{}",
+        m.mutation_description(),
+        file,
+        m.location_in_file(),
+        m.context_description_in_brackets()
+    );
+    match status {
+        MutantStatus::NotCovered => writeln!(
+            result,
+            "      <failure type=\"not_covered\" message=\"mutant not covered by any test\">{}</failure>",
+            escape(&description),
+        )?,
+        MutantStatus::Survived => writeln!(
+            result,
+            "      <failure type=\"survived\" message=\"mutant survived\">{}</failure>",
+            escape(&description),
+        )?,
+        MutantStatus::Killed(_) => {
+            if let Some(kill_details) = kill_details {
+                writeln!(
+                    result,
+                    "      <system-out>{}</system-out>",
+                    escape(&kill_description(kill_details))
+                )?;
+            }
+        }
+        MutantStatus::Timeout => {
+            result += "      <system-out>killed by timeout</system-out>\n";
+        }
+        MutantStatus::LimitExceeded(limit) => {
+            writeln!(
+                result,
+                "      <system-out>killed by exceeding the {} limit</system-out>",
+                limit
+            )?;
+        }
+        MutantStatus::Skipped => result += "      <skipped/>\n",
+        MutantStatus::Unviable => {
+            result += "      <skipped message=\"unviable mutant, an optimistic assumption failed\"/>\n"
+        }
+        MutantStatus::Ignored => {
+            result += "      <skipped message=\"equivalent mutant, ignored in mutagen-ignore\"/>\n"
+        }
+    }
+    result += "    </testcase>\n";
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::mutation_with;
+
+    #[test]
+    fn junit_testsuite_per_file() {
+        let mut report = MutagenReport::new();
+        report.add_mutation_result(
+            mutation_with(1, |m| m.stub_code("<", ">").stub_location("3:4-3:5")),
+            MutantStatus::Survived,
+        );
+        report.add_mutation_result(
+            mutation_with(2, |m| m.stub_code("<=", ">").stub_location("3:4-3:5")),
+            MutantStatus::Timeout,
+        );
+        report.add_mutation_result(
+            mutation_with(3, |m| m.stub_code(">=", ">").stub_location("3:4-3:5")),
+            MutantStatus::Skipped,
+        );
+
+        let xml = junit_xml(&report).unwrap();
+
+        assert!(xml
+            .contains("<testsuite name=\"src/lib.rs\" tests=\"3\" failures=\"1\" skipped=\"1\">"));
+        assert!(xml.contains(
+            "<failure type=\"survived\" message=\"mutant survived\">\
+             replace `&lt;` with `&gt;` at src/lib.rs@3:4-3:5(fn foo)</failure>"
+        ));
+        assert!(xml.contains("<system-out>killed by timeout</system-out>"));
+        assert!(xml.contains("<skipped/>"));
+    }
+}
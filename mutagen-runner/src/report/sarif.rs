@@ -0,0 +1,162 @@
+//! Report of the mutation analysis in SARIF 2.1 format.
+//!
+//! Each survived or not covered mutant is a result, the mutator is its rule.
+//! Source files are given relative to the workspace root, which is the base uri `SRCROOT`.
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use mutagen_core::comm::{self, BakedMutation, MutagenReport, MutantStatus};
+
+use super::slash_path;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Writes the report to `target/mutagen/report.sarif`.
+pub fn write_sarif_report(report: &MutagenReport) -> Result<PathBuf> {
+    let report_file = comm::get_report_file_sarif()?;
+    let log = sarif_log(report, &comm::workspace_root()?);
+    serde_json::to_writer_pretty(BufWriter::new(File::create(&report_file)?), &log)?;
+    Ok(report_file)
+}
+
+fn sarif_log(report: &MutagenReport, workspace_root: &Path) -> Value {
+    let undetected = report
+        .mutant_results()
+        .into_iter()
+        .filter(|(_, s)| matches!(s, MutantStatus::Survived | MutantStatus::NotCovered))
+        .collect::<Vec<_>>();
+
+    // one rule per mutator, indexed in alphabetical order
+    let rule_indices = undetected
+        .iter()
+        .map(|(m, _)| (m.mutator_name(), 0))
+        .collect::<BTreeMap<_, _>>()
+        .into_keys()
+        .enumerate()
+        .map(|(i, mutator)| (mutator, i))
+        .collect::<BTreeMap<_, _>>();
+    let rules = rule_indices
+        .keys()
+        .map(|mutator| {
+            json!({
+                "id": mutator,
+                "shortDescription": { "text": format!("mutation by mutator `{}`", mutator) },
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let results = undetected
+        .iter()
+        .map(|(m, s)| result(m, *s, rule_indices[m.mutator_name()]))
+        .collect::<Vec<_>>();
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "mutagen",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/llogiq/mutagen",
+                    "rules": rules,
+                }
+            },
+            "originalUriBaseIds": {
+                "SRCROOT": { "uri": format!("file://{}/", slash_path(workspace_root)) },
+            },
+            // columns of mutations are counted in characters
+            "columnKind": "unicodeCodePoints",
+            "results": results,
+        }]
+    })
+}
+
+/// renders a single undetected mutant as result
+fn result(m: &BakedMutation, status: MutantStatus, rule_index: usize) -> Value {
+    let mut physical_location = json!({
+        "artifactLocation": {
+            "uri": slash_path(m.source_file()),
+            "uriBaseId": "SRCROOT",
+        },
+    });
+    // SARIF columns are 1-based, the end column is exclusive in both formats
+    if let Ok(location) = m.location() {
+        physical_location["region"] = json!({
+            "startLine": location.start_line,
+            "startColumn": location.start_column + 1,
+            "endLine": location.end_line,
+            "endColumn": location.end_column + 1,
+        });
+    }
+    json!({
+        "ruleId": m.mutator_name(),
+        "ruleIndex": rule_index,
+        "level": "warning",
+        "message": {
+            "text": format!(
+                "mutant {}: {}{}",
+                status,
+                m.mutation_description(),
+                m.context_description_in_brackets()
+            ),
+        },
+        "locations": [{ "physicalLocation": physical_location }],
+        "properties": {
+            "mutationId": m.id(),
+            "mutantStatus": status.to_string(),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::mutation_with;
+
+    #[test]
+    fn sarif_results_of_undetected_mutants() {
+        let mut report = MutagenReport::new();
+        report.add_mutation_result(
+            mutation_with(1, |m| m.stub_location("3:4-3:5")),
+            MutantStatus::Survived,
+        );
+        report.add_mutation_result(
+            mutation_with(2, |m| m.stub_mutator("lit_int").stub_location("5:8-6:0")),
+            MutantStatus::Killed(Some(101)),
+        );
+        report.add_mutation_result(
+            mutation_with(3, |m| m.stub_location("stub")),
+            MutantStatus::NotCovered,
+        );
+
+        let log = sarif_log(&report, Path::new("/ws"));
+        let run = &log["runs"][0];
+
+        assert_eq!(run["originalUriBaseIds"]["SRCROOT"]["uri"], "file:///ws/");
+        assert_eq!(run["tool"]["driver"]["rules"][0]["id"], "binop_cmp");
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), 1);
+
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["ruleId"], "binop_cmp");
+        assert_eq!(
+            results[0]["message"]["text"],
+            "mutant SURVIVED: replace `<` with `>`(fn foo)"
+        );
+        let location = &results[0]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "src/lib.rs");
+        assert_eq!(
+            location["region"],
+            json!({ "startLine": 3, "startColumn": 5, "endLine": 3, "endColumn": 6 })
+        );
+        assert!(results[1]["locations"][0]["physicalLocation"]
+            .get("region")
+            .is_none());
+    }
+}
@@ -0,0 +1,159 @@
+//! Report of the mutation analysis in the JSON schema of mutation-testing-elements.
+//!
+//! This format is used by Stryker and can be displayed by the mutation-testing-elements report viewer.
+//! Each source file is contained with its source code and its mutants.
+//! Skipped and ignored mutants are reported as `Ignored`, unviable mutants as `CompileError`, since they could not be written in real source code.
+//! Mutants that exceeded a resource limit are reported as `RuntimeError`.
+
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use mutagen_core::comm::{self, BakedMutation, KillDetails, MutagenReport, MutantStatus};
+
+use super::{kill_description, read_source, slash_path};
+
+const SCHEMA_VERSION: &str = "1";
+
+/// Writes the report to `target/mutagen/stryker.json`.
+pub fn write_stryker_report(report: &MutagenReport) -> Result<PathBuf> {
+    let report_file = comm::get_report_file_stryker()?;
+    let workspace_root = comm::workspace_root()?;
+    let json = stryker_report(report, |file| read_source(&workspace_root, file));
+    serde_json::to_writer_pretty(BufWriter::new(File::create(&report_file)?), &json)?;
+    Ok(report_file)
+}
+
+fn stryker_report(report: &MutagenReport, read_source: impl Fn(&Path) -> Option<String>) -> Value {
+    let mut files = Map::new();
+    for (file, mutations) in report.mutations_per_file() {
+        let mutants = mutations
+            .into_iter()
+            .map(|(m, s)| mutant(m, s, report.kill_details(m.id())))
+            .collect::<Vec<_>>();
+        files.insert(
+            slash_path(file),
+            json!({
+                "language": "rust",
+                "source": read_source(file).unwrap_or_default(),
+                "mutants": mutants,
+            }),
+        );
+    }
+
+    json!({
+        "schemaVersion": SCHEMA_VERSION,
+        "thresholds": { "high": 80, "low": 60 },
+        "files": files,
+        "framework": {
+            "name": "mutagen",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+    })
+}
+
+/// renders a single mutant with its location and status
+fn mutant(m: &BakedMutation, status: MutantStatus, kill_details: Option<&KillDetails>) -> Value {
+    // mutation-testing-elements uses 1-based columns, the end is exclusive in both formats.
+    // Mutations without a valid location are placed at the start of the file.
+    let (start, end) = match m.location() {
+        Ok(l) => (
+            (l.start_line, l.start_column + 1),
+            (l.end_line, l.end_column + 1),
+        ),
+        Err(_) => ((1, 1), (1, 1)),
+    };
+    let mut mutant = json!({
+        "id": m.id().to_string(),
+        "mutatorName": m.mutator_name(),
+        "replacement": m.mutated_code(),
+        "description": format!(
+            "{}{}",
+            m.mutation_description(),
+            m.context_description_in_brackets()
+        ),
+        "location": {
+            "start": { "line": start.0, "column": start.1 },
+            "end": { "line": end.0, "column": end.1 },
+        },
+        "status": stryker_status(status),
+    });
+    let status_reason = match status {
+        MutantStatus::Killed(_) => kill_details.map(kill_description),
+        MutantStatus::Timeout => Some("killed by timeout".to_owned()),
+        MutantStatus::LimitExceeded(limit) => {
+            Some(format!("killed by exceeding the {} limit", limit))
+        }
+        MutantStatus::Unviable => Some("optimistic assumption of the mutator failed".to_owned()),
+        MutantStatus::Ignored => Some("equivalent mutant, ignored in mutagen-ignore".to_owned()),
+        _ => None,
+    };
+    if let Some(status_reason) = status_reason {
+        mutant["statusReason"] = status_reason.into();
+    }
+    mutant
+}
+
+fn stryker_status(status: MutantStatus) -> &'static str {
+    match status {
+        MutantStatus::NotCovered => "NoCoverage",
+        MutantStatus::Survived => "Survived",
+        MutantStatus::Killed(_) => "Killed",
+        MutantStatus::Timeout => "Timeout",
+        MutantStatus::LimitExceeded(_) => "RuntimeError",
+        MutantStatus::Skipped | MutantStatus::Ignored => "Ignored",
+        MutantStatus::Unviable => "CompileError",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::{mutation, mutation_with};
+
+    #[test]
+    fn stryker_files_with_source_and_mutants() {
+        let mut report = MutagenReport::new();
+        report.add_mutation_result(mutation(1), MutantStatus::Survived);
+        report.add_mutation_result(mutation(2), MutantStatus::Killed(Some(101)));
+        report.add_kill_details(
+            2,
+            KillDetails {
+                failed_tests: vec!["tests::foo".to_owned()],
+                panic_message: None,
+            },
+        );
+        report.add_mutation_result(
+            mutation_with(3, |m| m.stub_source_file("src/main.rs")),
+            MutantStatus::NotCovered,
+        );
+
+        let json = stryker_report(&report, |file| Some(format!("// {}", file.display())));
+
+        assert_eq!(json["schemaVersion"], "1");
+        let lib = &json["files"]["src/lib.rs"];
+        assert_eq!(lib["source"], "// src/lib.rs");
+        assert_eq!(
+            lib["mutants"][0],
+            json!({
+                "id": "1",
+                "mutatorName": "binop_cmp",
+                "replacement": ">",
+                "description": "replace `<` with `>`(fn foo)",
+                "location": {
+                    "start": { "line": 1, "column": 5 },
+                    "end": { "line": 1, "column": 6 },
+                },
+                "status": "Survived",
+            })
+        );
+        assert_eq!(lib["mutants"][1]["status"], "Killed");
+        assert_eq!(lib["mutants"][1]["statusReason"], "killed by tests::foo");
+        assert_eq!(
+            json["files"]["src/main.rs"]["mutants"][0]["status"],
+            "NoCoverage"
+        );
+    }
+}
@@ -0,0 +1,220 @@
+//! Checks the mutation score against the configured thresholds.
+
+use anyhow::{bail, Context, Result};
+use glob::{MatchOptions, Pattern};
+use std::path::Path;
+
+use mutagen_core::comm::{MutagenReport, ReportSummary};
+
+use super::config::ScoreConfig;
+
+/// Minimal mutation scores for the whole run and for single source files.
+#[derive(Debug)]
+pub struct ScoreThresholds {
+    min_score: Option<f64>,
+    ignore_not_covered: bool,
+    file_min_scores: Vec<(Pattern, f64)>,
+}
+
+impl ScoreThresholds {
+    /// Creates the thresholds from the configuration file.
+    ///
+    /// The given options from the command line take precedence over the configuration.
+    pub fn new(
+        min_score: Option<f64>,
+        ignore_not_covered: Option<bool>,
+        config: &ScoreConfig,
+    ) -> Result<Self> {
+        let file_min_scores = config
+            .files
+            .iter()
+            .map(|(pattern, min_score)| {
+                let pattern = Pattern::new(pattern)
+                    .with_context(|| format!("invalid file pattern `{}`", pattern))?;
+                check_min_score(*min_score)
+                    .with_context(|| format!("invalid minimum for `{}`", pattern))?;
+                Ok((pattern, *min_score))
+            })
+            .collect::<Result<_>>()?;
+        let min_score = min_score.or(config.min);
+        if let Some(min_score) = min_score {
+            check_min_score(min_score)?;
+        }
+        Ok(Self {
+            min_score,
+            ignore_not_covered: ignore_not_covered.unwrap_or(config.ignore_not_covered),
+            file_min_scores,
+        })
+    }
+
+    /// Checks the report against all thresholds.
+    ///
+    /// Returns a description for each score that is below its threshold.
+    pub fn check(&self, report: &MutagenReport) -> Vec<String> {
+        let mut failures = vec![];
+
+        if let Some(min_score) = self.min_score {
+            let score = self.score(&report.summary());
+            if score < min_score {
+                failures.push(format!(
+                    "mutation score {:.2}% is below the minimum of {:.2}%",
+                    score, min_score
+                ));
+            }
+        }
+
+        for (file, mutations) in report.mutations_per_file() {
+            let min_score = match self.min_score_for_file(file) {
+                Some(min_score) => min_score,
+                None => continue,
+            };
+            let mut summary = ReportSummary::default();
+            for (_, s) in mutations {
+                summary.add_mutation_result(s);
+            }
+            let score = self.score(&summary);
+            if score < min_score {
+                failures.push(format!(
+                    "mutation score {:.2}% of {} is below the minimum of {:.2}%",
+                    score,
+                    file.display(),
+                    min_score
+                ));
+            }
+        }
+
+        failures
+    }
+
+    fn score(&self, summary: &ReportSummary) -> f64 {
+        if self.ignore_not_covered {
+            summary.mutation_score_covered()
+        } else {
+            summary.mutation_score()
+        }
+    }
+
+    /// returns the highest minimal score of all patterns matching the given file.
+    fn min_score_for_file(&self, file: &Path) -> Option<f64> {
+        let match_options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        self.file_min_scores
+            .iter()
+            .filter(|(pattern, _)| pattern.matches_path_with(file, match_options))
+            .map(|(_, min_score)| *min_score)
+            .fold(None, |max, s| Some(max.map_or(s, |max: f64| max.max(s))))
+    }
+}
+
+/// Parses a minimal mutation score in percent from the command line.
+pub fn parse_min_score(min_score: &str) -> Result<f64> {
+    let min_score = min_score
+        .parse()
+        .with_context(|| format!("invalid mutation score `{}`", min_score))?;
+    check_min_score(min_score)?;
+    Ok(min_score)
+}
+
+/// fails if the minimal score is not a percentage between 0 and 100
+fn check_min_score(min_score: f64) -> Result<()> {
+    if !(0.0..=100.0).contains(&min_score) {
+        bail!(
+            "invalid mutation score `{}`, expected a percentage between 0 and 100",
+            min_score
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mutagen_core::comm::MutantStatus;
+
+    fn score_config(min: Option<f64>, files: &[(&str, f64)]) -> ScoreConfig {
+        ScoreConfig {
+            min,
+            ignore_not_covered: false,
+            files: files.iter().map(|(p, s)| (p.to_string(), *s)).collect(),
+        }
+    }
+
+    #[test]
+    fn cli_overrides_config() {
+        let thresholds =
+            ScoreThresholds::new(Some(50.0), None, &score_config(Some(80.0), &[])).unwrap();
+
+        assert_eq!(thresholds.min_score, Some(50.0));
+    }
+
+    #[test]
+    fn cli_overrides_ignore_not_covered_of_config() {
+        let config = ScoreConfig {
+            ignore_not_covered: true,
+            ..score_config(None, &[])
+        };
+
+        let thresholds = ScoreThresholds::new(None, None, &config).unwrap();
+        assert!(thresholds.ignore_not_covered);
+        let thresholds = ScoreThresholds::new(None, Some(false), &config).unwrap();
+        assert!(!thresholds.ignore_not_covered);
+        let thresholds = ScoreThresholds::new(None, Some(true), &score_config(None, &[])).unwrap();
+        assert!(thresholds.ignore_not_covered);
+    }
+
+    #[test]
+    fn parse_min_score_in_percent() {
+        assert_eq!(parse_min_score("80").unwrap(), 80.0);
+        assert_eq!(parse_min_score("0").unwrap(), 0.0);
+        assert_eq!(parse_min_score("100").unwrap(), 100.0);
+        assert!(parse_min_score("100.5").is_err());
+        assert!(parse_min_score("-1").is_err());
+        assert!(parse_min_score("NaN").is_err());
+        assert!(parse_min_score("high").is_err());
+    }
+
+    #[test]
+    fn reject_invalid_min_scores_of_config() {
+        assert!(ScoreThresholds::new(None, None, &score_config(Some(120.0), &[])).is_err());
+        assert!(ScoreThresholds::new(None, None, &score_config(Some(f64::NAN), &[])).is_err());
+        assert!(
+            ScoreThresholds::new(None, None, &score_config(None, &[("src/*.rs", -5.0)])).is_err()
+        );
+    }
+
+    #[test]
+    fn score_ignoring_not_covered() {
+        let mut summary = ReportSummary::default();
+        summary.add_mutation_result(MutantStatus::Killed(None));
+        summary.add_mutation_result(MutantStatus::Survived);
+        summary.add_mutation_result(MutantStatus::NotCovered);
+        summary.add_mutation_result(MutantStatus::NotCovered);
+
+        let config = score_config(None, &[]);
+        let thresholds = ScoreThresholds::new(None, None, &config).unwrap();
+        assert_eq!(thresholds.score(&summary), 25.0);
+        let thresholds = ScoreThresholds::new(None, Some(true), &config).unwrap();
+        assert_eq!(thresholds.score(&summary), 50.0);
+    }
+
+    #[test]
+    fn highest_min_score_of_matching_files() {
+        let config = score_config(None, &[("src/*.rs", 50.0), ("src/parser.rs", 90.0)]);
+        let thresholds = ScoreThresholds::new(None, None, &config).unwrap();
+
+        assert_eq!(
+            thresholds.min_score_for_file(Path::new("src/parser.rs")),
+            Some(90.0)
+        );
+        assert_eq!(
+            thresholds.min_score_for_file(Path::new("src/lib.rs")),
+            Some(50.0)
+        );
+        assert_eq!(
+            thresholds.min_score_for_file(Path::new("src/a/lib.rs")),
+            None
+        );
+    }
+}
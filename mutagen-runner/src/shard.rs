@@ -0,0 +1,176 @@
+//! Splitting of a mutation run into several shards and merging the partial reports of the shards.
+//!
+//! The mutations are assigned to the shards by their id.
+//! Each shard only tests its own mutations and writes a partial report containing only these mutations.
+
+use anyhow::{bail, Context, Result};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::str::FromStr;
+
+use mutagen_core::comm::{JsonReport, MutagenReport, JSON_REPORT_SCHEMA_VERSION};
+
+/// The `index`-th of `count` shards, where `index` is 1-based.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Shard {
+    index: usize,
+    count: usize,
+}
+
+impl Shard {
+    /// Checks if the mutation with the given id belongs to this shard.
+    pub fn contains(self, mutation_id: usize) -> bool {
+        mutation_id % self.count == self.index - 1
+    }
+}
+
+impl FromStr for Shard {
+    type Err = anyhow::Error;
+
+    /// parses a shard in the format `k/n`
+    fn from_str(s: &str) -> Result<Self> {
+        let (index, count) = s
+            .split_once('/')
+            .with_context(|| format!("invalid shard `{}`, expected `k/n`", s))?;
+        let index = index
+            .parse::<usize>()
+            .with_context(|| format!("invalid shard index `{}`", index))?;
+        let count = count
+            .parse::<usize>()
+            .with_context(|| format!("invalid number of shards `{}`", count))?;
+        if index == 0 || index > count {
+            bail!("shard index has to be between 1 and {}", count);
+        }
+        Ok(Self { index, count })
+    }
+}
+
+/// Reads a report written to `target/mutagen/report.json`.
+pub fn read_json_report(path: &Path) -> Result<JsonReport> {
+    let reader = BufReader::new(
+        File::open(path).with_context(|| format!("unable to open {}", path.display()))?,
+    );
+    let report = serde_json::from_reader(reader)
+        .with_context(|| format!("invalid report {}", path.display()))?;
+    parse_json_report(report).with_context(|| format!("invalid report {}", path.display()))
+}
+
+/// Checks the schema version of the report before parsing it.
+///
+/// Reports of older versions can be read, since each version only added statuses and fields.
+fn parse_json_report(report: serde_json::Value) -> Result<JsonReport> {
+    let schema_version = report["schema_version"].as_u64().unwrap_or(0);
+    if schema_version == 0 || schema_version > u64::from(JSON_REPORT_SCHEMA_VERSION) {
+        bail!(
+            "unsupported schema version {}, this version of cargo-mutagen supports up to version {}",
+            report["schema_version"],
+            JSON_REPORT_SCHEMA_VERSION
+        );
+    }
+    Ok(serde_json::from_value(report)?)
+}
+
+/// Combines the partial reports of several shards into a single report.
+///
+/// Each mutation must be contained in only one of the reports.
+pub fn merge_reports(reports: Vec<JsonReport>) -> Result<MutagenReport> {
+    let mut mutagen_report = MutagenReport::new();
+    let mut ids = HashSet::new();
+    for report in reports {
+        for result in report.mutations {
+            if !ids.insert(result.id) {
+                bail!("mutation {} is contained in several reports", result.id);
+            }
+            if let Some(kill_details) = result.kill_details() {
+                mutagen_report.add_kill_details(result.id, kill_details);
+            }
+            mutagen_report.add_mutation_result(result.mutation(), result.mutant_status());
+        }
+    }
+    Ok(mutagen_report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mutagen_core::comm::{MutantStatus, Mutation};
+
+    fn json_report(results: &[(usize, MutantStatus)]) -> JsonReport {
+        let mut report = MutagenReport::new();
+        for (id, status) in results {
+            let mutation = Mutation::new_stub()
+                .stub_source_file("src/lib.rs")
+                .stub_location(&format!("{}:0-{}:1", id, id))
+                .with_id(*id, *id);
+            report.add_mutation_result(mutation, *status);
+        }
+        JsonReport::new(&report)
+    }
+
+    #[test]
+    fn parse_shard() {
+        assert_eq!(
+            "2/5".parse::<Shard>().unwrap(),
+            Shard { index: 2, count: 5 }
+        );
+        assert!("0/5".parse::<Shard>().is_err());
+        assert!("6/5".parse::<Shard>().is_err());
+        assert!("2".parse::<Shard>().is_err());
+    }
+
+    #[test]
+    fn shards_partition_mutations() {
+        let shards = (1..=3)
+            .map(|i| Shard { index: i, count: 3 })
+            .collect::<Vec<_>>();
+        for id in 1..=10 {
+            assert_eq!(shards.iter().filter(|s| s.contains(id)).count(), 1);
+        }
+    }
+
+    #[test]
+    fn merge_shard_reports() {
+        let report1 = json_report(&[
+            (1, MutantStatus::Killed(Some(101))),
+            (3, MutantStatus::Survived),
+        ]);
+        let report2 = json_report(&[(2, MutantStatus::NotCovered)]);
+
+        let summary = merge_reports(vec![report1, report2]).unwrap().summary();
+
+        assert_eq!(summary.num_mutations(), 3);
+        assert_eq!(summary.killed(), 1);
+        assert_eq!(summary.survived(), 2);
+        assert_eq!(summary.not_covered(), 1);
+    }
+
+    #[test]
+    fn reject_newer_schema_version() {
+        let report = serde_json::json!({
+            "schema_version": JSON_REPORT_SCHEMA_VERSION + 1,
+            "summary": {},
+            "mutations": [{ "status": "some_new_status" }],
+        });
+
+        let err = parse_json_report(report).unwrap_err().to_string();
+        assert!(err.contains("unsupported schema version"), "{}", err);
+    }
+
+    #[test]
+    fn read_older_schema_version() {
+        let mut report = serde_json::to_value(json_report(&[(1, MutantStatus::Survived)])).unwrap();
+        report["schema_version"] = 1.into();
+
+        assert_eq!(parse_json_report(report).unwrap().mutations.len(), 1);
+    }
+
+    #[test]
+    fn merge_duplicate_mutation() {
+        let report1 = json_report(&[(1, MutantStatus::Survived)]);
+        let report2 = json_report(&[(1, MutantStatus::Survived)]);
+
+        assert!(merge_reports(vec![report1, report2]).is_err());
+    }
+}
@@ -0,0 +1,200 @@
+//! Suppression of equivalent mutants via the file `mutagen-ignore` in the workspace root.
+//!
+//! Equivalent mutants behave exactly like the original program and can therefore never be killed.
+//! The file lists such mutants in TOML format, each with the reason why it is ignored.
+//! Mutants are identified by their source file, function, `impl`-block, mutator and original and mutated code,
+//! since mutation ids change whenever code is added or removed.
+//!
+//! ```toml
+//! [[ignore]]
+//! file = "src/buffer.rs"
+//! fn = "new"
+//! impl = "Buffer"
+//! mutator = "lit_int"
+//! original = "16"
+//! mutated = "17"
+//! reason = "the capacity is only a hint"
+//! ```
+//!
+//! The fields `fn` and `impl` are optional, if omitted, the entry matches mutations in any function or `impl`-block.
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+
+use mutagen_core::comm::{self, BakedMutation};
+
+const SUPPRESSION_FILE: &str = "mutagen-ignore";
+
+/// Entries of the suppression file
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Suppressions {
+    #[serde(default, rename = "ignore")]
+    entries: Vec<Suppression>,
+}
+
+/// A single entry of the suppression file, matching equivalent mutants
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct Suppression {
+    /// source file relative to the workspace root
+    file: PathBuf,
+    #[serde(rename = "fn")]
+    fn_name: Option<String>,
+    #[serde(rename = "impl")]
+    impl_name: Option<String>,
+    mutator: String,
+    original: String,
+    mutated: String,
+    /// why the matching mutants are equivalent
+    reason: String,
+}
+
+impl Suppressions {
+    /// Reads the suppression file `mutagen-ignore` in the workspace root.
+    ///
+    /// If this file does not exist, no mutants are ignored.
+    pub fn load() -> Result<Self> {
+        let path = comm::workspace_root()?.join(SUPPRESSION_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let suppressions = fs::read_to_string(&path)?;
+        Self::parse(&suppressions).with_context(|| format!("invalid {}", path.display()))
+    }
+
+    fn parse(suppressions: &str) -> Result<Self> {
+        let suppressions: Self = toml::from_str(suppressions)?;
+        if let Some(s) = suppressions
+            .entries
+            .iter()
+            .find(|s| s.reason.trim().is_empty())
+        {
+            bail!("missing reason for ignoring {}", s);
+        }
+        Ok(suppressions)
+    }
+
+    /// Checks if the given mutant is ignored by any entry.
+    pub fn is_ignored(&self, m: &BakedMutation) -> bool {
+        self.entries.iter().any(|s| s.matches(m))
+    }
+
+    /// Returns the entries that do not match any of the given mutations.
+    pub fn stale_entries(&self, mutations: &[BakedMutation]) -> Vec<&Suppression> {
+        self.entries
+            .iter()
+            .filter(|s| !mutations.iter().any(|m| s.matches(m)))
+            .collect()
+    }
+}
+
+impl Suppression {
+    fn matches(&self, m: &BakedMutation) -> bool {
+        self.file == m.source_file()
+            && self.mutator == m.mutator_name()
+            && self.original == m.original_code()
+            && self.mutated == m.mutated_code()
+            && (self.fn_name.is_none() || self.fn_name.as_deref() == m.fn_name())
+            && (self.impl_name.is_none() || self.impl_name.as_deref() == m.impl_name())
+    }
+}
+
+impl fmt::Display for Suppression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "`{}` -> `{}` by {} in {}",
+            self.original,
+            self.mutated,
+            self.mutator,
+            self.file.display()
+        )?;
+        match (&self.fn_name, &self.impl_name) {
+            (Some(fn_name), Some(impl_name)) => write!(f, " (fn {}::{})", impl_name, fn_name),
+            (Some(fn_name), None) => write!(f, " (fn {})", fn_name),
+            (None, Some(impl_name)) => write!(f, " (impl {})", impl_name),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::mutation_with;
+
+    #[test]
+    fn ignore_matching_mutants() {
+        let suppressions = Suppressions::parse(
+            r#"
+            [[ignore]]
+            file = "src/buffer.rs"
+            fn = "new"
+            mutator = "lit_int"
+            original = "16"
+            mutated = "17"
+            reason = "the capacity is only a hint"
+
+            [[ignore]]
+            file = "src/buffer.rs"
+            impl = "Other"
+            mutator = "lit_int"
+            original = "16"
+            mutated = "15"
+            reason = "stale"
+            "#,
+        )
+        .unwrap();
+        let mutations = vec![
+            mutation_with(1, |m| {
+                m.stub_source_file("src/buffer.rs")
+                    .stub_impl_name("Buffer")
+                    .stub_fn_name("new")
+                    .stub_mutator("lit_int")
+                    .stub_code("16", "17")
+            }),
+            mutation_with(1, |m| {
+                m.stub_source_file("src/buffer.rs")
+                    .stub_impl_name("Buffer")
+                    .stub_fn_name("with_capacity")
+                    .stub_mutator("lit_int")
+                    .stub_code("16", "17")
+            }),
+            mutation_with(1, |m| {
+                m.stub_source_file("src/buffer.rs")
+                    .stub_impl_name("Buffer")
+                    .stub_fn_name("new")
+                    .stub_mutator("lit_int")
+                    .stub_code("16", "15")
+            }),
+        ];
+
+        assert!(suppressions.is_ignored(&mutations[0]));
+        assert!(!suppressions.is_ignored(&mutations[1]));
+        assert!(!suppressions.is_ignored(&mutations[2]));
+
+        let stale = suppressions.stale_entries(&mutations);
+        assert_eq!(stale.len(), 1);
+        assert_eq!(
+            stale[0].to_string(),
+            "`16` -> `15` by lit_int in src/buffer.rs (impl Other)"
+        );
+    }
+
+    #[test]
+    fn reason_is_required() {
+        let entry = r#"
+            [[ignore]]
+            file = "src/buffer.rs"
+            mutator = "lit_int"
+            original = "16"
+            mutated = "17"
+            "#;
+        assert!(Suppressions::parse(entry).is_err());
+        assert!(Suppressions::parse(&format!("{}reason = \" \"", entry)).is_err());
+    }
+}
@@ -1,54 +1,131 @@
 use anyhow::{bail, Result};
+use std::collections::BTreeSet;
+use std::ffi::OsStr;
 use std::fs;
-use std::io::Write;
-use std::path::Path;
-use std::process::{Command, Stdio};
+use std::io::{Read, Write};
+#[cfg(unix)]
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::thread;
 use std::time::{Duration, Instant};
 
+use sha2::{Digest, Sha256};
 use wait_timeout::ChildExt;
 
-use mutagen_core::comm::{self, BakedMutation, CoverageCollection, CoverageHit, MutantStatus};
+use mutagen_core::comm::{
+    self, BakedMutation, CoverageCollection, CoverageHit, KillDetails, MutantStatus,
+};
 
-use super::Progress;
+use super::nextest::{self, parse_nextest_kill_details, NextestBuild};
+use super::test_output::parse_kill_details;
+use super::{Progress, ResourceLimits, TimeoutPolicy};
+
+/// the run of the first tests of a test suite gets this fraction of the timeout of the whole test suite
+const FIRST_TESTS_TIMEOUT_DIVISOR: u32 = 2;
+
+/// A test suite that can be run against the mutations
+#[derive(Debug, Copy, Clone)]
+pub enum TestSuite<'a> {
+    /// a test-binary built by `cargo test --no-run`
+    Bin(&'a Path),
+    /// the doctests of the library, run by `cargo test --doc` with the given arguments for cargo
+    Doctests(&'a [&'a OsStr]),
+    /// all test-binaries of a build of cargo-nextest, run by `cargo nextest run`
+    Nextest(&'a NextestBuild),
+}
 
 /// wrapper around a test-binary that can be executed
 #[derive(Debug)]
 pub struct TestBin<'a> {
     id: usize,
-    pub bin_path: &'a Path,
+    suite: TestSuite<'a>,
+    /// arguments passed to every run of the test-binary
+    test_args: &'a [String],
+    /// test filters among the arguments
+    filters: TestFilters,
 }
 
 // wrapper around a test-binary, which has been run already and its runtime has been timed.
 #[derive(Debug)]
 pub struct TestBinTested<'a> {
     test_bin: TestBin<'a>,
-    exe_time: Duration,
+    /// baseline runtime of the unmutated test suite
+    pub exe_time: Duration,
+    timeout: Duration,
     pub coverage: CoverageCollection,
 }
 
 impl<'a> TestBin<'a> {
-    pub fn new(bin_path: &'a Path, id: usize) -> Self {
-        Self { id, bin_path }
+    pub fn new(suite: TestSuite<'a>, id: usize, test_args: &'a [String]) -> Self {
+        Self {
+            id,
+            suite,
+            test_args,
+            filters: TestFilters::parse(test_args),
+        }
+    }
+
+    /// name of the test suite as displayed in the progress
+    pub fn name(&self) -> String {
+        match self.suite {
+            TestSuite::Bin(bin_path) => bin_path.display().to_string(),
+            TestSuite::Doctests(_) => "doctests".to_owned(),
+            TestSuite::Nextest(_) => "nextest".to_owned(),
+        }
+    }
+
+    /// creates the command that runs the test suite with the arguments for the test harness
+    fn command(&self) -> Command {
+        let mut command = match self.suite {
+            TestSuite::Bin(bin_path) => Command::new(bin_path),
+            TestSuite::Doctests(cargo_args) => {
+                let mut command = Command::new("cargo");
+                command.args(["test", "--doc"]).args(cargo_args).arg("--");
+                // the doctests are compiled by cargo, which reports its progress on stderr
+                command.stderr(Stdio::null());
+                // cargo runs the doctests in child processes, which are killed together with cargo on a timeout
+                #[cfg(unix)]
+                command.process_group(0);
+                command
+            }
+            // the test arguments are translated for nextest by the build
+            TestSuite::Nextest(build) => return build.command(),
+        };
+        command.args(self.test_args);
+        command
     }
 
     // run the test and record the covered mutators and the time required to run the tests.
+    //
+    // Coverage is only recorded for the given mutations, which might be a subset of all `num_mutations` mutations.
+    // The tests are run again without recording coverage until the number of baseline runs of the timeout policy is reached.
     pub fn run_test(
         self,
         progress: &mut Progress,
+        num_mutations: usize,
         mutations: &[BakedMutation],
+        timeout_policy: &TimeoutPolicy,
     ) -> Result<TestBinTested<'a>> {
-        let num_mutations = mutations.len();
         let test_start = Instant::now();
 
-        progress.start_testsuite_unmutated(self.bin_path, self.id)?;
+        progress.start_testsuite_unmutated(&self.name(), self.id)?;
 
         ::std::io::stdout().flush()?;
 
+        // each test suite writes to its own coverage file
+        let coverage_file = comm::get_coverage_file_for_testsuite(self.id)?;
+        if coverage_file.exists() {
+            // remove leftovers from previous runs
+            fs::remove_file(&coverage_file)?;
+        }
+
         // run test suite
-        let mut command = Command::new(self.bin_path);
+        let mut command = self.command();
         command.env("MUTAGEN_MODE", "coverage");
         command.env("MUTAGEN_NUM_MUTATIONS", format!("{}", num_mutations));
-        command.env("MUTAGEN_TESTSUITE", &self.bin_path);
+        command.env("MUTAGEN_TESTSUITE", self.name());
+        command.env("MUTAGEN_COVERAGE_FILE", &coverage_file);
         command.stdout(Stdio::null());
         let mut test_run = command.spawn()?;
         let status = test_run.wait()?;
@@ -60,9 +137,20 @@ impl<'a> TestBin<'a> {
             bail!("test suite fails. Retry after `cargo test` succeeds");
         }
 
+        let mut exe_times = vec![exe_time];
+        for _ in 1..timeout_policy.baseline_runs() {
+            let test_start = Instant::now();
+            let status = self.command().stdout(Stdio::null()).status()?;
+            if !status.success() {
+                bail!("test suite fails without mutations. Retry after `cargo test` succeeds reliably");
+            }
+            exe_times.push(test_start.elapsed());
+        }
+        let exe_time = timeout_policy.baseline(&exe_times);
+        let timeout = timeout_policy.timeout(exe_time);
+
         // read the coverage-file for this testsuite and delete it afterwards
         let coverage = {
-            let coverage_file = comm::get_coverage_file()?;
             if !coverage_file.exists() {
                 // no coverage file means that no mutations has been covered
                 CoverageCollection::new_empty(num_mutations)
@@ -81,11 +169,21 @@ impl<'a> TestBin<'a> {
             test_bin: self,
             coverage,
             exe_time,
+            timeout,
         })
     }
 }
 
 impl<'a> TestBinTested<'a> {
+    pub fn suite(&self) -> TestSuite<'a> {
+        self.test_bin.suite
+    }
+
+    /// name of the test suite as displayed in the progress
+    pub fn name(&self) -> String {
+        self.test_bin.name()
+    }
+
     /// Checks if any mutation is covered.
     ///
     /// Returns false, if no mutation is covered by the testsuite
@@ -93,29 +191,444 @@ impl<'a> TestBinTested<'a> {
         self.coverage.num_covered() != 0
     }
 
-    pub fn check_mutant(&self, mutation: &BakedMutation) -> Result<MutantStatus> {
+    /// Runs the test suite with the given mutation activated.
+    ///
+    /// If `only_covering_tests` is set, only the tests that cover the mutation are executed.
+    /// The tests in `first_tests` are run on their own before the remaining tests, since they are likely to kill the mutant.
+    /// They get only a share of the timeout, and if they time out, all tests are run again with the full timeout.
+    /// The names of the selected tests are appended to the arguments of the test-binary, together with `--exact`.
+    pub fn check_mutant(
+        &self,
+        mutation: &BakedMutation,
+        only_covering_tests: bool,
+        first_tests: &[String],
+        limits: &ResourceLimits,
+    ) -> Result<(MutantStatus, Option<KillDetails>)> {
+        let covering_tests = if only_covering_tests {
+            if !self.coverage.is_covered(mutation.id()) {
+                // no test of this test suite can kill the mutant
+                return Ok((MutantStatus::Survived, None));
+            }
+            self.coverage.covering_tests(mutation.id())
+        } else {
+            None
+        };
+
+        // tests outside of the filters of the user must not be selected
+        let first_tests = first_tests
+            .iter()
+            .filter(|t| covering_tests.is_none_or(|c| c.contains(*t)))
+            .filter(|t| self.test_bin.filters.matches(t))
+            .collect::<Vec<_>>();
+        let mut first_tests_timed_out = false;
+        if !first_tests.is_empty() {
+            let selection = TestSelection::Only(first_tests.clone());
+            let timeout = self.timeout / FIRST_TESTS_TIMEOUT_DIVISOR;
+            let result = self.run_mutant(mutation, selection, timeout, limits)?;
+            match result.0 {
+                MutantStatus::Survived => {}
+                MutantStatus::Timeout => first_tests_timed_out = true,
+                _ => return Ok(result),
+            }
+        }
+
+        let tested = if first_tests_timed_out {
+            vec![]
+        } else {
+            first_tests
+        };
+        match remaining_tests(&self.test_bin.filters, covering_tests, tested) {
+            Some(selection) => self.run_mutant(mutation, selection, self.timeout, limits),
+            None => Ok((MutantStatus::Survived, None)),
+        }
+    }
+
+    /// Runs the selected tests of the test suite with the given mutation activated.
+    ///
+    /// The test suite is killed after the given timeout.
+    /// If the mutant is killed, the failed tests and the panic message are taken from the output of the test suite.
+    /// If the mutant creates its unviable marker file, it is unviable regardless of the result of the test suite.
+    /// The test suite runs with the given resource limits, a mutant exceeding them is killed by the exceeded limit.
+    fn run_mutant(
+        &self,
+        mutation: &BakedMutation,
+        selection: TestSelection,
+        timeout: Duration,
+        limits: &ResourceLimits,
+    ) -> Result<(MutantStatus, Option<KillDetails>)> {
+        let mut command = self.test_bin.command();
+        command.args(selection.args());
+
+        let unviable_file = comm::get_unviable_marker_file(mutation.id())?;
+        if unviable_file.exists() {
+            // remove leftovers from previous runs
+            fs::remove_file(&unviable_file)?;
+        }
+
         // run command and wait for its output
-        let mut command = Command::new(self.test_bin.bin_path);
         command.env("MUTATION_ID", mutation.id().to_string());
-        command.stdout(Stdio::null());
-        command.stderr(Stdio::null());
+        command.env("MUTAGEN_UNVIABLE_FILE", &unviable_file);
+        command.stdout(Stdio::piped());
+        command.stderr(Stdio::piped());
+        limits.apply(&mut command);
         let mut test_run = command.spawn()?;
+        let stdout = read_in_background(test_run.stdout.take());
+        let stderr = read_in_background(test_run.stderr.take());
 
-        let wait_time = 5 * self.exe_time + Duration::from_millis(500);
-        let timeout = test_run.wait_timeout(wait_time)?;
+        let timeout = test_run.wait_timeout(timeout)?;
+        if timeout.is_none() {
+            match self.test_bin.suite {
+                TestSuite::Nextest(_) => nextest::terminate(&mut test_run)?,
+                TestSuite::Doctests(_) => kill_process_group(&mut test_run)?,
+                TestSuite::Bin(_) => test_run.kill()?,
+            }
+        }
+        if unviable_file.exists() {
+            fs::remove_file(&unviable_file)?;
+            return Ok((MutantStatus::Unviable, None));
+        }
 
         Ok(match timeout {
             Some(status) => {
                 if status.success() {
-                    MutantStatus::Survived
+                    (MutantStatus::Survived, None)
                 } else {
-                    MutantStatus::Killed(status.code())
+                    let stdout = stdout.join().unwrap_or_default();
+                    let stderr = stderr.join().unwrap_or_default();
+                    let kill_details = match self.test_bin.suite {
+                        TestSuite::Nextest(_) => parse_nextest_kill_details(&stdout),
+                        _ => parse_kill_details(&stdout, &stderr),
+                    };
+                    let mutant_status = match limits.exceeded(status, &stdout, &stderr) {
+                        Some(limit) => MutantStatus::LimitExceeded(limit),
+                        None => MutantStatus::Killed(status.code()),
+                    };
+                    (mutant_status, Some(kill_details))
                 }
             }
-            None => {
-                test_run.kill()?;
-                MutantStatus::Timeout
-            }
+            None => (MutantStatus::Timeout, None),
         })
     }
 }
+
+/// tests of a test suite that are run against a mutant
+#[derive(Debug, PartialEq, Eq)]
+enum TestSelection<'t> {
+    All,
+    /// only the given tests
+    Only(Vec<&'t String>),
+    /// all tests except the given ones
+    Skip(Vec<&'t String>),
+}
+
+impl TestSelection<'_> {
+    /// arguments appended to the arguments of the test-binary to select the tests
+    fn args(&self) -> Vec<&str> {
+        let mut args = vec![];
+        match self {
+            Self::All => return args,
+            Self::Only(tests) => args.extend(tests.iter().map(|t| t.as_str())),
+            Self::Skip(tests) => {
+                for test in tests {
+                    args.push("--skip");
+                    args.push(test.as_str());
+                }
+            }
+        }
+        args.push("--exact");
+        args
+    }
+}
+
+/// Selects the tests that still have to be run against a mutant after the given tests.
+///
+/// Returns `None` if no tests remain.
+/// `--exact` also applies to the filters of the user, so `--skip` is only used if this does not change which tests the filters select.
+fn remaining_tests<'t>(
+    filters: &TestFilters,
+    covering_tests: Option<&'t BTreeSet<String>>,
+    tested: Vec<&'t String>,
+) -> Option<TestSelection<'t>> {
+    match covering_tests {
+        Some(tests) => {
+            let remaining = tests
+                .iter()
+                .filter(|t| !tested.contains(t))
+                .collect::<Vec<_>>();
+            if remaining.is_empty() {
+                None
+            } else {
+                Some(TestSelection::Only(remaining))
+            }
+        }
+        None if tested.is_empty() || !filters.allow_exact() => Some(TestSelection::All),
+        None => Some(TestSelection::Skip(tested)),
+    }
+}
+
+/// The test filters of the user among the arguments of the test-binary.
+///
+/// Libtest also applies `--exact` to the filters of the user.
+/// Tests selected by their exact names must therefore be selected by these filters as well.
+#[derive(Debug, Default)]
+struct TestFilters {
+    /// names of the tests to run, a test is run if its name contains any of them
+    names: Vec<String>,
+    /// the names have to match the names of the tests exactly
+    exact: bool,
+}
+
+impl TestFilters {
+    /// options of libtest that take a value, which is not a filter
+    const OPTIONS_WITH_VALUE: &'static [&'static str] = &[
+        "--skip",
+        "--test-threads",
+        "--format",
+        "--color",
+        "--logfile",
+        "--shuffle-seed",
+        "-Z",
+    ];
+
+    fn parse(test_args: &[String]) -> Self {
+        let mut filters = Self::default();
+        let mut test_args = test_args.iter();
+        while let Some(arg) = test_args.next() {
+            if Self::OPTIONS_WITH_VALUE.contains(&arg.as_str()) {
+                test_args.next();
+            } else if arg == "--exact" {
+                filters.exact = true;
+            } else if !arg.starts_with('-') {
+                filters.names.push(arg.clone());
+            }
+        }
+        filters
+    }
+
+    /// Checks if the filters select the given test.
+    fn matches(&self, test: &str) -> bool {
+        self.names.is_empty()
+            || self.names.iter().any(|name| {
+                if self.exact {
+                    name == test
+                } else {
+                    test.contains(name.as_str())
+                }
+            })
+    }
+
+    /// Checks if `--exact` can be added without changing which tests the filters select.
+    fn allow_exact(&self) -> bool {
+        self.names.is_empty() || self.exact
+    }
+}
+
+/// reads the output of a child process in a separate thread to avoid blocking the child on a full pipe
+fn read_in_background<R: Read + Send + 'static>(pipe: Option<R>) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut output = vec![];
+        if let Some(mut pipe) = pipe {
+            // a read error only results in incomplete details
+            let _ = pipe.read_to_end(&mut output);
+        }
+        String::from_utf8_lossy(&output).into_owned()
+    })
+}
+
+/// Kills a process and all processes in its process group.
+///
+/// Killing only cargo would leave the doctests it runs behind.
+fn kill_process_group(child: &mut Child) -> Result<()> {
+    #[cfg(unix)]
+    {
+        // the child has not been waited for yet, so its pid cannot have been reused
+        if unsafe { libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL) } == 0 {
+            return Ok(());
+        }
+    }
+    child.kill()?;
+    Ok(())
+}
+
+/// Collects the files of the given directory that can contain doctests, sorted by their path.
+///
+/// These are the rust files and the markdown files, which can be included as documentation.
+/// Hidden directories and the target directory are skipped.
+fn doctest_sources(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut sources = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if path.is_dir() {
+            if name != "target" && !name.starts_with('.') {
+                sources.extend(doctest_sources(&path)?);
+            }
+        } else if name.ends_with(".rs") || name.ends_with(".md") {
+            sources.push(path);
+        }
+    }
+    sources.sort();
+    Ok(sources)
+}
+
+/// computes a combined hash of the contents of all test binaries and the arguments they are run with
+///
+/// The doctests are hashed by the sources of the workspace that can contain them.
+pub fn hash_test_bins(test_bins: &[TestBinTested]) -> Result<String> {
+    let mut hasher = Sha256::new();
+    for bin in test_bins {
+        match bin.suite() {
+            TestSuite::Bin(bin_path) => hasher.update(fs::read(bin_path)?),
+            TestSuite::Doctests(cargo_args) => {
+                hasher.update("doctests");
+                for arg in cargo_args {
+                    hasher.update(arg.to_string_lossy().as_bytes());
+                }
+                let workspace_root = comm::workspace_root()?;
+                for source in doctest_sources(&workspace_root)? {
+                    let content = fs::read(&source)?;
+                    hasher.update(
+                        source
+                            .strip_prefix(&workspace_root)?
+                            .to_string_lossy()
+                            .as_bytes(),
+                    );
+                    hasher.update(content.len().to_le_bytes());
+                    hasher.update(content);
+                }
+            }
+            TestSuite::Nextest(build) => {
+                for bin_path in &build.binaries {
+                    hasher.update(fs::read(bin_path)?);
+                }
+            }
+        }
+        for arg in bin.test_bin.test_args {
+            hasher.update(arg.len().to_le_bytes());
+            hasher.update(arg);
+        }
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn doctest_command() {
+        let cargo_args = [OsStr::new("--features"), OsStr::new("a")];
+        let test_args = strings(&["parser"]);
+        let bin = TestBin::new(TestSuite::Doctests(&cargo_args), 1, &test_args);
+        let command = bin.command();
+
+        assert_eq!(command.get_program(), "cargo");
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            vec!["test", "--doc", "--features", "a", "--", "parser"]
+        );
+    }
+
+    #[test]
+    fn bin_command() {
+        let test_args = strings(&["parser", "--test-threads", "1"]);
+        let bin = TestBin::new(
+            TestSuite::Bin(Path::new("target/debug/deps/foo")),
+            1,
+            &test_args,
+        );
+        let command = bin.command();
+
+        assert_eq!(command.get_program(), "target/debug/deps/foo");
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            vec!["parser", "--test-threads", "1"]
+        );
+    }
+
+    #[test]
+    fn sources_of_doctests() {
+        let dir = std::env::temp_dir().join(format!("mutagen-doctests-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for file in [
+            "README.md",
+            "Cargo.toml",
+            "src/lib.rs",
+            "src/a/b.rs",
+            "target/debug/build/out.rs",
+            ".git/x.rs",
+        ] {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        let sources = doctest_sources(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            sources,
+            vec![
+                dir.join("README.md"),
+                dir.join("src/a/b.rs"),
+                dir.join("src/lib.rs")
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_test_filters() {
+        let filters = TestFilters::parse(&strings(&[
+            "parser",
+            "--test-threads",
+            "2",
+            "--skip",
+            "slow",
+            "lexer",
+        ]));
+
+        assert_eq!(filters.names, strings(&["parser", "lexer"]));
+        assert!(filters.matches("tests::parser_error"));
+        assert!(!filters.matches("tests::slow"));
+        assert!(!filters.allow_exact());
+
+        let exact = TestFilters::parse(&strings(&["--exact", "tests::parser"]));
+        assert!(exact.matches("tests::parser"));
+        assert!(!exact.matches("tests::parser_error"));
+        assert!(exact.allow_exact());
+    }
+
+    #[test]
+    fn first_tests_with_user_filter() {
+        let first = strings(&["tests::parser_a"]);
+        let first = first.iter().collect::<Vec<_>>();
+
+        // `parser --skip tests::parser_a --exact` would only run a test named `parser`
+        let filters = TestFilters::parse(&strings(&["parser"]));
+        let selection = remaining_tests(&filters, None, first.clone()).unwrap();
+        assert_eq!(selection, TestSelection::All);
+        assert!(selection.args().is_empty());
+
+        let filters = TestFilters::parse(&strings(&["--test-threads", "1"]));
+        let selection = remaining_tests(&filters, None, first.clone()).unwrap();
+        assert_eq!(
+            selection.args(),
+            vec!["--skip", "tests::parser_a", "--exact"]
+        );
+
+        let covering = strings(&["tests::parser_a", "tests::parser_b"])
+            .into_iter()
+            .collect::<BTreeSet<_>>();
+        let filters = TestFilters::parse(&strings(&["parser"]));
+        let selection = remaining_tests(&filters, Some(&covering), first.clone()).unwrap();
+        assert_eq!(selection.args(), vec!["tests::parser_b", "--exact"]);
+        assert_eq!(
+            remaining_tests(&filters, Some(&covering), covering.iter().collect()),
+            None
+        );
+    }
+}
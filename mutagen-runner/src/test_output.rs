@@ -0,0 +1,184 @@
+//! Parsing of the output of test suites that use the default test harness (libtest).
+
+use mutagen_core::comm::KillDetails;
+
+/// maximal length of the panic message stored in the report, in characters
+const MAX_PANIC_MESSAGE_LEN: usize = 500;
+
+/// Extracts the names of the failed tests and the first panic message from the output of a test suite.
+///
+/// Panics in tests are reported on stdout, panics outside of tests on stderr.
+pub fn parse_kill_details(stdout: &str, stderr: &str) -> KillDetails {
+    let failed_tests = stdout
+        .lines()
+        .filter_map(|line| line.strip_prefix("test ")?.strip_suffix(" ... FAILED"))
+        .map(|test| test.to_owned())
+        .collect();
+    let panic_message = panic_message(stdout).or_else(|| panic_message(stderr));
+    KillDetails {
+        failed_tests,
+        panic_message,
+    }
+}
+
+/// finds the first panic message in the output.
+///
+/// The message consists of everything after `panicked at` up to the next empty line, note or backtrace.
+/// This includes the location of the panic.
+pub fn panic_message(output: &str) -> Option<String> {
+    let mut lines = output.lines();
+    let first_line = lines.find_map(|line| {
+        // newer versions of rust print the thread id after the thread name
+        let (_, rest) = line.strip_prefix("thread '")?.split_once(" panicked at ")?;
+        Some(rest)
+    })?;
+
+    let mut message = first_line.to_owned();
+    for line in lines {
+        if line.is_empty()
+            || line.starts_with("note: ")
+            || line.starts_with("stack backtrace:")
+            || line.starts_with("---- ")
+        {
+            break;
+        }
+        message.push('\n');
+        message.push_str(line);
+    }
+    Some(truncate(message.trim_end()))
+}
+
+fn truncate(message: &str) -> String {
+    match message.char_indices().nth(MAX_PANIC_MESSAGE_LEN) {
+        Some((end, _)) => format!("{}...", &message[..end]),
+        None => message.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_failed_tests() {
+        let stdout = "\
+running 3 tests
+test tests::a ... ok
+test tests::b ... FAILED
+test tests::c ... FAILED
+
+failures:
+
+---- tests::b stdout ----
+thread 'tests::b' panicked at src/lib.rs:10:9:
+assertion `left == right` failed
+  left: 1
+ right: 2
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+
+---- tests::c stdout ----
+thread 'tests::c' panicked at src/lib.rs:20:9:
+explicit panic
+
+failures:
+    tests::b
+    tests::c
+
+test result: FAILED. 1 passed; 2 failed; 0 ignored; 0 measured; 0 filtered out
+";
+        let details = parse_kill_details(stdout, "");
+
+        assert_eq!(details.failed_tests, vec!["tests::b", "tests::c"]);
+        assert_eq!(
+            details.panic_message.as_deref(),
+            Some("src/lib.rs:10:9:\nassertion `left == right` failed\n  left: 1\n right: 2")
+        );
+    }
+
+    #[test]
+    fn parse_failed_doctests() {
+        let stdout = "\
+running 2 tests
+test src/lib.rs - add (line 1) ... FAILED
+test src/lib.rs - sub (line 6) ... ok
+
+failures:
+
+---- src/lib.rs - add (line 1) stdout ----
+Test executable failed (exit status: 101).
+
+stderr:
+
+thread 'main' (2848) panicked at src/lib.rs:5:1:
+assertion `left == right` failed
+  left: 3
+ right: 4
+stack backtrace:
+   0: __rustc::rust_begin_unwind
+
+
+
+failures:
+    src/lib.rs - add (line 1)
+
+test result: FAILED. 1 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.20s
+";
+        let details = parse_kill_details(stdout, "");
+
+        assert_eq!(details.failed_tests, vec!["src/lib.rs - add (line 1)"]);
+        assert_eq!(
+            details.panic_message.as_deref(),
+            Some("src/lib.rs:5:1:\nassertion `left == right` failed\n  left: 3\n right: 4")
+        );
+    }
+
+    #[test]
+    fn panic_message_with_thread_id_and_backtrace() {
+        let stdout = "\
+---- tests::c stdout ----
+thread 'tests::c' (1234) panicked at src/lib.rs:20:9:
+explicit panic
+stack backtrace:
+   0: rust_begin_unwind
+";
+        assert_eq!(
+            panic_message(stdout).as_deref(),
+            Some("src/lib.rs:20:9:\nexplicit panic")
+        );
+    }
+
+    #[test]
+    fn parse_old_panic_format() {
+        let stdout = "\
+---- tests::b stdout ----
+thread 'tests::b' panicked at 'explicit panic', src/lib.rs:20:9
+note: run with `RUST_BACKTRACE=1` environment variable to display a backtrace
+";
+        assert_eq!(
+            panic_message(stdout).as_deref(),
+            Some("'explicit panic', src/lib.rs:20:9")
+        );
+    }
+
+    #[test]
+    fn panic_message_from_stderr() {
+        let stderr = "thread 'main' panicked at src/main.rs:1:1:\nfailure\n";
+        let details = parse_kill_details("", stderr);
+
+        assert!(details.failed_tests.is_empty());
+        assert_eq!(
+            details.panic_message.as_deref(),
+            Some("src/main.rs:1:1:\nfailure")
+        );
+    }
+
+    #[test]
+    fn truncate_long_panic_message() {
+        let message = "x".repeat(MAX_PANIC_MESSAGE_LEN + 10);
+
+        assert_eq!(
+            truncate(&message),
+            format!("{}...", "x".repeat(MAX_PANIC_MESSAGE_LEN))
+        );
+    }
+}
@@ -0,0 +1,21 @@
+//! Helpers shared by the tests of the runner.
+
+use mutagen_core::comm::{BakedMutation, Mutation};
+
+/// Creates a stub mutation with the given id.
+///
+/// The mutation replaces `<` by `>` in the function `foo` on line `id` of `src/lib.rs`.
+pub fn mutation(id: usize) -> BakedMutation {
+    mutation_with(id, |m| m)
+}
+
+/// Creates a stub mutation like `mutation`, adjusted with the `stub_*` methods of `Mutation`.
+pub fn mutation_with(id: usize, adjust: impl FnOnce(Mutation) -> Mutation) -> BakedMutation {
+    let stub = Mutation::new_stub()
+        .stub_source_file("src/lib.rs")
+        .stub_fn_name("foo")
+        .stub_mutator("binop_cmp")
+        .stub_code("<", ">")
+        .stub_location(&format!("{}:4-{}:5", id, id));
+    adjust(stub).with_id(id, id)
+}
@@ -0,0 +1,236 @@
+//! Policy for the time after which a test suite with an active mutation is considered to be timed out.
+//!
+//! The timeout is computed from the runtime of the unmutated test suite as `multiplier * baseline + slack`,
+//! bounded by an optional absolute maximum.
+//! The baseline is the median runtime of several unmutated runs of the test suite.
+//! When several mutants are tested in parallel, the baseline is multiplied by the number of jobs,
+//! since the baseline is measured by a single run while the jobs compete for the same processors.
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::time::Duration;
+use structopt::StructOpt;
+
+use super::config::cli_flag;
+
+const DEFAULT_MULTIPLIER: f64 = 5.0;
+const DEFAULT_SLACK: Duration = Duration::from_millis(500);
+
+/// Command line options for the timeout policy
+#[derive(StructOpt, Debug)]
+pub struct TimeoutOptions {
+    /// Factor of the runtime of the unmutated test suite after which a mutant times out [default: 5]
+    #[structopt(long, name = "FACTOR")]
+    timeout_multiplier: Option<f64>,
+
+    /// Fixed time added to the timeout of mutants, e.g. `500ms` [default: 500ms]
+    #[structopt(long, name = "DURATION")]
+    timeout_slack: Option<humantime::Duration>,
+
+    /// Absolute upper bound of the timeout of mutants, e.g. `2m`
+    #[structopt(long, name = "MAX_DURATION")]
+    timeout_max: Option<humantime::Duration>,
+
+    /// Number of unmutated runs of each test suite, whose median runtime is used for the timeout [default: 1]
+    #[structopt(long, name = "RUNS")]
+    baseline_runs: Option<usize>,
+
+    /// Run timed out mutants again before reporting them as killed by timeout
+    #[structopt(long, overrides_with = "no-retry-timeouts")]
+    retry_timeouts: bool,
+
+    /// Do not run timed out mutants again, even if the configuration says so
+    #[structopt(long, overrides_with = "retry-timeouts")]
+    no_retry_timeouts: bool,
+}
+
+/// Timeout policy in the configuration file
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct TimeoutConfig {
+    pub multiplier: Option<f64>,
+    /// fixed time added to the timeout, e.g. `"500ms"`
+    pub slack: Option<String>,
+    /// absolute upper bound of the timeout, e.g. `"2m"`
+    pub max: Option<String>,
+    pub baseline_runs: Option<usize>,
+    pub retry: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct TimeoutPolicy {
+    multiplier: f64,
+    slack: Duration,
+    max: Option<Duration>,
+    baseline_runs: usize,
+    retry_timeouts: bool,
+    /// number of mutants tested in parallel
+    jobs: usize,
+}
+
+impl TimeoutPolicy {
+    /// Creates the policy from the configuration file.
+    ///
+    /// The options from the command line take precedence over the configuration.
+    pub fn new(opt: &TimeoutOptions, config: &TimeoutConfig) -> Result<Self> {
+        let multiplier = opt
+            .timeout_multiplier
+            .or(config.multiplier)
+            .unwrap_or(DEFAULT_MULTIPLIER);
+        if multiplier.is_nan() || multiplier < 0.0 {
+            bail!("invalid timeout multiplier `{}`", multiplier);
+        }
+        let slack = match (opt.timeout_slack, &config.slack) {
+            (Some(slack), _) => slack.into(),
+            (None, Some(slack)) => parse_duration(slack)?,
+            (None, None) => DEFAULT_SLACK,
+        };
+        let max = match (opt.timeout_max, &config.max) {
+            (Some(max), _) => Some(max.into()),
+            (None, Some(max)) => Some(parse_duration(max)?),
+            (None, None) => None,
+        };
+        let baseline_runs = opt.baseline_runs.or(config.baseline_runs).unwrap_or(1);
+        if baseline_runs == 0 {
+            bail!("the baseline requires at least one run of the test suites");
+        }
+
+        Ok(Self {
+            multiplier,
+            slack,
+            max,
+            baseline_runs,
+            retry_timeouts: cli_flag(opt.retry_timeouts, opt.no_retry_timeouts)
+                .unwrap_or(config.retry),
+            jobs: 1,
+        })
+    }
+
+    /// Scales the timeout for testing the given number of mutants in parallel.
+    pub fn with_jobs(self, jobs: usize) -> Self {
+        Self { jobs, ..self }
+    }
+
+    /// number of unmutated runs of each test suite
+    pub fn baseline_runs(&self) -> usize {
+        self.baseline_runs
+    }
+
+    /// Checks if timed out mutants should be run again.
+    pub fn retry_timeouts(&self) -> bool {
+        self.retry_timeouts
+    }
+
+    /// Computes the baseline from the runtimes of the unmutated test suite.
+    pub fn baseline(&self, exe_times: &[Duration]) -> Duration {
+        median(exe_times)
+    }
+
+    /// Computes the timeout for a test suite with the given baseline runtime.
+    pub fn timeout(&self, baseline: Duration) -> Duration {
+        let timeout = baseline.mul_f64(self.multiplier * self.jobs as f64) + self.slack;
+        match self.max {
+            Some(max) => timeout.min(max),
+            None => timeout,
+        }
+    }
+}
+
+fn parse_duration(duration: &str) -> Result<Duration> {
+    humantime::parse_duration(duration).with_context(|| format!("invalid duration `{}`", duration))
+}
+
+/// median of the given durations. The median of an even number of durations is the mean of both middle values.
+fn median(durations: &[Duration]) -> Duration {
+    let mut durations = durations.to_vec();
+    durations.sort_unstable();
+    let mid = durations.len() / 2;
+    if durations.is_empty() {
+        Duration::ZERO
+    } else if 2 * mid == durations.len() {
+        (durations[mid - 1] + durations[mid]) / 2
+    } else {
+        durations[mid]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_options() -> TimeoutOptions {
+        TimeoutOptions::from_iter(&["test"])
+    }
+
+    #[test]
+    fn default_timeout() {
+        let policy = TimeoutPolicy::new(&default_options(), &TimeoutConfig::default()).unwrap();
+
+        assert_eq!(
+            policy.timeout(Duration::from_secs(1)),
+            Duration::from_millis(5500)
+        );
+    }
+
+    #[test]
+    fn timeout_is_capped() {
+        let config = TimeoutConfig {
+            multiplier: Some(10.0),
+            max: Some("3s".to_owned()),
+            ..TimeoutConfig::default()
+        };
+        let policy = TimeoutPolicy::new(&default_options(), &config).unwrap();
+
+        assert_eq!(
+            policy.timeout(Duration::from_millis(100)),
+            Duration::from_millis(1500)
+        );
+        assert_eq!(
+            policy.timeout(Duration::from_secs(1)),
+            Duration::from_secs(3)
+        );
+    }
+
+    #[test]
+    fn timeout_is_scaled_by_jobs() {
+        let policy = TimeoutPolicy::new(&default_options(), &TimeoutConfig::default())
+            .unwrap()
+            .with_jobs(4);
+
+        assert_eq!(
+            policy.timeout(Duration::from_secs(1)),
+            Duration::from_millis(20500)
+        );
+    }
+
+    #[test]
+    fn cli_overrides_config() {
+        let opt = TimeoutOptions::from_iter(&[
+            "test",
+            "--timeout-multiplier",
+            "2",
+            "--timeout-slack",
+            "1s",
+        ]);
+        let config = TimeoutConfig {
+            multiplier: Some(10.0),
+            slack: Some("5s".to_owned()),
+            ..TimeoutConfig::default()
+        };
+        let policy = TimeoutPolicy::new(&opt, &config).unwrap();
+
+        assert_eq!(
+            policy.timeout(Duration::from_secs(1)),
+            Duration::from_secs(3)
+        );
+    }
+
+    #[test]
+    fn median_of_runtimes() {
+        let ms = Duration::from_millis;
+
+        assert_eq!(median(&[ms(30), ms(10), ms(20)]), ms(20));
+        assert_eq!(median(&[ms(30), ms(10), ms(20), ms(100)]), ms(25));
+        assert_eq!(median(&[ms(10)]), ms(10));
+    }
+}